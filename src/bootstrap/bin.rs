@@ -1,6 +1,15 @@
+use std::path::{Path, PathBuf};
+
 use bevy::prelude::App;
 
-use crate::{fuzz_runner, parse_commands};
+use crate::{
+    check_determinism, encode_commands, export_text, fuzz_dictionary, fuzz_runner, migrate,
+    minimize, parse_commands_lenient, parse_input_file, prelude::FuzzInput,
+    text::parse_input_file_with, DeterminismRegistry, ParseError,
+};
+
+/// Default number of runs in `check-determinism` mode
+const DETERMINISM_RUNS: usize = 3;
 
 use super::FuzzTarget;
 
@@ -19,28 +28,9 @@ pub fn bin_bootstrap(mut app_builder: impl FuzzTarget, mut args: std::env::Args)
         }
         "view" => {
             println!("FUZZ: print recorded input");
-            let path = match args.next() {
-                Some(path) => std::path::PathBuf::from(path),
-                None => {
-                    println!("\tplease supply the event .bin file as second argument");
-                    return;
-                }
-            };
-
-            let contents = match std::fs::read(&path) {
-                Ok(c) => c,
-                Err(e) => {
-                    println!("\terror reading file {:?}: {:?}", path, e);
-                    return;
-                }
-            };
-
-            let data = match parse_commands(contents) {
-                Ok(val) => val,
-                Err(e) => {
-                    println!("\terror parsing input commands: {:?}", e);
-                    return;
-                }
+            let data = match read_commands(args.next(), parse_fuzzed_input_file) {
+                Some(data) => data,
+                None => return,
             };
 
             println!("FUZZ INPUT: {:#?}", data);
@@ -52,36 +42,205 @@ pub fn bin_bootstrap(mut app_builder: impl FuzzTarget, mut args: std::env::Args)
         }
         "apply" => {
             println!("FUZZ: applying input events");
-            let path = match args.next() {
-                Some(path) => std::path::PathBuf::from(path),
-                None => {
-                    println!("\tplease supply the event .bin file as second argument");
+            let data = match read_commands(args.next(), parse_fuzzed_input_file) {
+                Some(data) => data,
+                None => return,
+            };
+
+            app_builder.enable_apply_mode(&mut app, data);
+            fuzz_runner(&mut app);
+        }
+        "check-determinism" => {
+            println!("FUZZ: checking that applying input events is deterministic");
+            let data = match read_commands(args.next(), parse_fuzzed_input_file) {
+                Some(data) => data,
+                None => return,
+            };
+            let runs = match args.next().map(|runs| runs.parse::<usize>()) {
+                None => DETERMINISM_RUNS,
+                Some(Ok(runs)) if runs >= 2 => runs,
+                Some(_) => {
+                    println!("\tplease supply at least 2 runs as third argument");
                     return;
                 }
             };
 
-            let contents = match std::fs::read(&path) {
-                Ok(c) => c,
-                Err(e) => {
-                    println!("\terror reading file {:?}: {:?}", path, e);
-                    return;
+            let mut registry = DeterminismRegistry::default();
+            app_builder.determinism(&mut registry);
+            app_builder.enable_apply_mode(&mut app, data.clone());
+
+            match check_determinism(&mut app, &registry, &data, runs) {
+                Ok(frames) => println!("\t{} runs of {} frames were identical", runs, frames),
+                Err(divergence) => println!("\tnot deterministic: {}", divergence),
+            }
+        }
+        "minimize" => {
+            println!("FUZZ: minimizing input events that cause a panic");
+            let input = args.next();
+            let output = match output_path(args.next()) {
+                Some(output) => output,
+                None => return,
+            };
+            let data = match read_commands(input, parse_fuzzed_input_file) {
+                Some(data) => data,
+                None => return,
+            };
+
+            app_builder.enable_apply_mode(&mut app, data.clone());
+
+            match minimize(&mut app, &data) {
+                Some((minimized, message)) => {
+                    println!("\tpanic: {}", message);
+                    println!(
+                        "\tshrunk {} input events to {}",
+                        data.len(),
+                        minimized.len()
+                    );
+                    write_output(&output, &minimized, encode_commands(&minimized));
                 }
+                None => println!("\tapplying the input events did not panic"),
+            }
+        }
+        "migrate" => {
+            println!("FUZZ: migrating recording to the current format");
+            let input = args.next();
+            let output = match output_path(args.next()) {
+                Some(output) => output,
+                None => return,
+            };
+            let data = match read_commands(input, migrate) {
+                Some(data) => data,
+                None => return,
+            };
+
+            write_output(&output, &data, encode_commands(&data));
+        }
+        "export" => {
+            println!("FUZZ: exporting recording to text");
+            let input = args.next();
+            let output = match output_path(args.next()) {
+                Some(output) => output,
+                None => return,
+            };
+            let data = match read_commands(input, parse_input_file) {
+                Some(data) => data,
+                None => return,
             };
 
-            let data = match parse_commands(contents) {
-                Ok(val) => val,
-                Err(e) => {
-                    println!("\terror parsing input commands: {:?}", e);
+            write_output(&output, &data, export_text(&data).into_bytes());
+        }
+        "import" => {
+            println!("FUZZ: importing recording from text");
+            let input = args.next();
+            let output = match output_path(args.next()) {
+                Some(output) => output,
+                None => return,
+            };
+            let data = match read_commands(input, parse_input_file) {
+                Some(data) => data,
+                None => return,
+            };
+
+            write_output(&output, &data, encode_commands(&data));
+        }
+        "dict" => {
+            println!("FUZZ: writing libFuzzer dictionary of encoded input events");
+            let path = match args.next() {
+                Some(path) => PathBuf::from(path),
+                None => {
+                    println!("\tplease supply the output file as second argument");
                     return;
                 }
             };
 
-            app_builder.enable_apply_mode(&mut app, data);
-            fuzz_runner(&mut app);
+            let dictionary = fuzz_dictionary();
+            let entries = dictionary
+                .lines()
+                .filter(|line| !line.starts_with('#'))
+                .count();
+
+            match std::fs::write(&path, dictionary) {
+                Ok(_) => println!("\twrote {} dictionary entries to {:?}", entries, path),
+                Err(e) => println!("\terror writing file {:?}: {:?}", path, e),
+            }
         }
         _ => {
-            println!("Please use 'record', 'apply [filename]', 'view [filename]' or 'gui' as a parameter");
-            return;
+            println!("Please use 'record', 'apply [filename]', 'check-determinism [filename] [runs]', 'minimize [filename] [out_filename]', 'view [filename]', 'migrate [filename] [out_filename]', 'export [filename] [out_filename]', 'import [filename] [out_filename]', 'dict [out_filename]' or 'gui' as a parameter");
+        }
+    }
+}
+
+/// Reads and parses the input file, printing the error if that fails
+fn read_commands(
+    path: Option<String>,
+    parse: fn(Vec<u8>) -> Result<Vec<FuzzInput>, ParseError>,
+) -> Option<Vec<FuzzInput>> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            println!("\tplease supply the event .bin (or text) file as second argument");
+            return None;
         }
+    };
+
+    let contents = match std::fs::read(&path) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("\terror reading file {:?}: {:?}", path, e);
+            return None;
+        }
+    };
+
+    match parse(contents) {
+        Ok(val) => Some(val),
+        Err(e) => {
+            println!("\terror parsing input commands: {}", e);
+            None
+        }
+    }
+}
+
+/// Parses the input file like `fuzz_bootstrap` does, skipping frames of binary recordings that
+/// cannot be decoded
+fn parse_fuzzed_input_file(input: Vec<u8>) -> Result<Vec<FuzzInput>, ParseError> {
+    parse_input_file_with(input, |input| {
+        let (inputs, dropped) = parse_commands_lenient(input)?;
+        if dropped > 0 {
+            println!(
+                "\tskipped {} input frames that could not be decoded",
+                dropped
+            );
+        }
+
+        Ok(inputs)
+    })
+}
+
+/// Reads the output file path, printing the error if it is missing or cannot be written to
+fn output_path(path: Option<String>) -> Option<PathBuf> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            println!("\tplease supply the output file as third argument");
+            return None;
+        }
+    };
+
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty());
+    if path.is_dir() || parent.filter(|parent| !parent.is_dir()).is_some() {
+        println!("\tcannot write output file {:?}", path);
+        return None;
+    }
+
+    Some(path)
+}
+
+/// Writes the converted recording into the output file
+fn write_output(path: &Path, data: &[FuzzInput], contents: Vec<u8>) {
+    match std::fs::write(path, contents) {
+        Ok(_) => println!("\twrote {} input events to {:?}", data.len(), path),
+        Err(e) => println!("\terror writing file {:?}: {:?}", path, e),
     }
 }
@@ -1,6 +1,9 @@
 use bevy::prelude::App;
 
-use crate::{fuzz_runner, parse_commands, prelude::FuzzData};
+use crate::{
+    fuzz_runner, parse_commands_lenient,
+    prelude::{FuzzData, FuzzInput},
+};
 
 use super::FuzzTarget;
 use std::sync::Once;
@@ -8,20 +11,26 @@ use std::sync::Once;
 static mut APP: Option<App> = None;
 static INIT: Once = Once::new();
 
-pub fn fuzz_bootstrap(mut app_builder: impl FuzzTarget, fuzz_bytes: &[u8]) {
+pub fn fuzz_bootstrap(app_builder: impl FuzzTarget, fuzz_bytes: &[u8]) {
     // if no fuzz bytes at all, do not continue
-    if fuzz_bytes.len() == 0 {
+    if fuzz_bytes.is_empty() {
         return;
     }
 
-    // try to parse the commands from the input
-    let fuzz_inputs = match parse_commands(fuzz_bytes.to_vec()) {
-        Ok(d) => d,
+    // try to parse the commands from the input, skipping frames broken by mutations
+    let fuzz_inputs = match parse_commands_lenient(fuzz_bytes.to_vec()) {
+        Ok((d, _)) => d,
         Err(_) => return,
     };
 
+    fuzz_bootstrap_inputs(app_builder, fuzz_inputs);
+}
+
+/// Structure-aware variant of `fuzz_bootstrap`, for fuzz targets taking the inputs directly
+/// (e.g. through `arbitrary`)
+pub fn fuzz_bootstrap_inputs(mut app_builder: impl FuzzTarget, fuzz_inputs: Vec<FuzzInput>) {
     // no commands at all parsed (garbage), do not continue
-    if fuzz_inputs.len() == 0 {
+    if fuzz_inputs.is_empty() {
         return;
     }
 
@@ -36,7 +45,7 @@ pub fn fuzz_bootstrap(mut app_builder: impl FuzzTarget, fuzz_bytes: &[u8]) {
         });
 
         // get ref to app
-        let app = APP.as_mut().unwrap();
+        let app = (*std::ptr::addr_of_mut!(APP)).as_mut().unwrap();
 
         // feed the fuzz inputs
         app.insert_resource(FuzzData::new(fuzz_inputs));
@@ -1,20 +1,25 @@
 use bevy::prelude::*;
+use std::{path::PathBuf, time::Duration};
 
 mod bin;
 mod fuzz;
 pub use bin::bin_bootstrap;
-pub use fuzz::fuzz_bootstrap;
+pub use fuzz::{fuzz_bootstrap, fuzz_bootstrap_inputs};
 
 use crate::{
     prelude::{FuzzData, FuzzInput},
-    EventOutputPlugin,
+    progress::RunnerProgress,
+    runner::{never_run, FuzzRunnerSettings},
+    time::{freeze_time_system, fuzz_clock_system, FuzzClock},
+    window::{FilePathPool, WindowIdMap},
+    DeterminismRegistry, EventOutputPlugin, InvariantRegistry, SnapshotRegistry, WorldSnapshot,
 };
 
 pub trait FuzzTarget: Plugin {
     fn add_headless_plugins(&mut self, app: &mut App) {
         app.add_plugins(MinimalPlugins)
-            .add_plugin(bevy::core::CorePlugin::default())
-            .add_plugin(bevy::input::InputPlugin::default())
+            .add_plugin(bevy::core::CorePlugin)
+            .add_plugin(bevy::input::InputPlugin)
             .add_plugin(bevy::window::WindowPlugin::default());
     }
 
@@ -22,6 +27,80 @@ pub trait FuzzTarget: Plugin {
         app.add_plugins(DefaultPlugins);
     }
 
+    /// Number of windows the fuzzed input events are spread over
+    ///
+    /// Window indices in the input are folded into this range, and a headless window is
+    /// created for each index on first use. Set to the number of windows your app opens.
+    fn window_count(&self) -> usize {
+        1
+    }
+
+    /// Simulated time between two frames while fuzzing or applying inputs
+    ///
+    /// A virtual clock (`FuzzClock`) advances by this delta on each frame. Bevy's `Time` is held
+    /// at zero meanwhile, so systems reading the time through `FuzzTime` instead of `Time` see the
+    /// frames pass and reproduce exactly.
+    fn frame_delta(&self) -> Duration {
+        Duration::from_nanos(16_666_667)
+    }
+
+    /// Pool of paths that fuzzed file drag-and-drop events pick from
+    ///
+    /// Override to add paths your app knows how to import. Recorded drag-and-drop events
+    /// keep their original paths.
+    fn file_path_candidates(&self) -> Vec<PathBuf> {
+        vec![
+            PathBuf::new(),
+            PathBuf::from("/"),
+            PathBuf::from("nonexistent/file.png"),
+            PathBuf::from("assets/ünïcödé/файл 文件 🎮.png"),
+            PathBuf::from("assets/\u{202e}gnp.exe"),
+            PathBuf::from(format!(
+                "{}file.png",
+                "very_long_directory_name/".repeat(200)
+            )),
+        ]
+    }
+
+    /// Whether sub-apps (`App::add_sub_app`) are updated on every fuzzed frame
+    fn tick_sub_apps(&self) -> bool {
+        false
+    }
+
+    /// Number of frames after which a fuzz run ends, even if there is input left
+    fn max_ticks(&self) -> Option<usize> {
+        None
+    }
+
+    /// Wall time after which a frame is considered stuck (e.g. in an infinite loop)
+    ///
+    /// The process is then aborted, reporting the stage that was running, so that the fuzzer
+    /// records the input as a crash.
+    ///
+    /// Only the running stage is tracked, not the running system: the report lists all systems
+    /// of that stage, which bevy may run in parallel.
+    fn max_tick_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Registers how the state of the built app is restored before each fuzz iteration
+    ///
+    /// Reflected components (`#[reflect(Component)]`) are restored by default. Resources (e.g.
+    /// inserted in `Plugin::build`) and other components that are not registered here keep their
+    /// state between iterations, and are listed when fuzzing starts. Common bevy input resources
+    /// and events are registered by default.
+    fn snapshot(&self, _registry: &mut SnapshotRegistry) {}
+
+    /// Registers invariant systems, checked after every fuzzed frame
+    ///
+    /// A violated invariant fails the run like a panic would.
+    fn invariants(&self, _registry: &mut InvariantRegistry) {}
+
+    /// Registers the components compared between runs in `check-determinism` mode
+    ///
+    /// Components registered as reflected (`#[reflect(Component)]`) are compared by default.
+    fn determinism(&self, _registry: &mut DeterminismRegistry) {}
+
     /// Internal: do not implement
     fn enable_gui_mode(&mut self, app: &mut App) {
         self.add_gui_plugins(app);
@@ -31,20 +110,72 @@ pub trait FuzzTarget: Plugin {
     /// Internal: do not implement
     fn enable_recording_mode(&mut self, app: &mut App) {
         self.add_gui_plugins(app);
-        app.add_plugin(EventOutputPlugin::default());
+        app.add_plugin(EventOutputPlugin);
         self.build(app);
     }
 
     /// Internal: do not implement
     fn enable_fuzzing_mode(&mut self, app: &mut App) {
         self.add_headless_plugins(app);
+        self.add_fuzz_resources(app);
         self.build(app);
+        self.capture_snapshot(app);
+        self.add_invariants(app);
+        self.add_progress_tracking(app);
     }
 
     /// Internal: do not implement
     fn enable_apply_mode(&mut self, app: &mut App, data: Vec<FuzzInput>) {
         self.add_headless_plugins(app);
+        self.add_fuzz_resources(app);
         app.insert_resource(FuzzData::new(data));
         self.build(app);
+        self.capture_snapshot(app);
+        self.add_invariants(app);
+        self.add_progress_tracking(app);
+    }
+
+    /// Internal: do not implement
+    fn add_fuzz_resources(&mut self, app: &mut App) {
+        app.insert_resource(WindowIdMap::new(self.window_count()))
+            .insert_resource(FilePathPool(self.file_path_candidates()))
+            .insert_resource(FuzzClock::new(self.frame_delta()))
+            .insert_resource(FuzzRunnerSettings {
+                tick_sub_apps: self.tick_sub_apps(),
+                max_ticks: self.max_ticks(),
+                max_tick_duration: self.max_tick_duration(),
+            })
+            .add_system_to_stage(CoreStage::First, fuzz_clock_system)
+            .add_system_to_stage(CoreStage::First, freeze_time_system);
+
+        // the fuzz runner runs the startup stage itself, on each reset
+        app.schedule
+            .stage(CoreStage::Startup, |schedule: &mut Schedule| {
+                schedule.set_run_criteria(never_run.system())
+            });
+    }
+
+    /// Internal: do not implement
+    fn capture_snapshot(&mut self, app: &mut App) {
+        let mut registry = SnapshotRegistry::default();
+        self.snapshot(&mut registry);
+
+        let snapshot = WorldSnapshot::capture(&app.world, &registry);
+        snapshot.report();
+        app.insert_resource(snapshot);
+    }
+
+    /// Internal: do not implement
+    fn add_invariants(&mut self, app: &mut App) {
+        let mut registry = InvariantRegistry::default();
+        self.invariants(&mut registry);
+
+        registry.initialize(&mut app.world);
+        app.insert_resource(registry);
+    }
+
+    /// Internal: do not implement
+    fn add_progress_tracking(&mut self, app: &mut App) {
+        RunnerProgress::install(app, self.max_tick_duration());
     }
 }
@@ -17,7 +17,7 @@ impl FuzzData {
     }
 
     pub(crate) fn iter_next(&self) -> impl Iterator<Item = &FuzzInput> {
-        let skip_count = self.last_index_id.map(|v| v).unwrap_or(0);
+        let skip_count = self.last_index_id.unwrap_or(0);
         self.fuzz_inputs.iter().skip(skip_count)
     }
 
@@ -51,23 +51,23 @@ mod tests {
     #[test]
     fn test() {
         let mut data = FuzzData::new(vec![FuzzInput::RunFrame]);
-        assert_eq!(data.is_finished(), false);
+        assert!(!data.is_finished());
         assert_eq!(data.iter_next().count(), 1);
         data.set_last_idx(None);
-        assert_eq!(data.is_finished(), true);
+        assert!(data.is_finished());
         assert_eq!(data.iter_next().count(), 0);
 
         let mut data = FuzzData::new(vec![FuzzInput::RunFrame, FuzzInput::RunFrame]);
-        assert_eq!(data.is_finished(), false);
+        assert!(!data.is_finished());
         assert_eq!(data.iter_next().count(), 2);
         assert_eq!(data.last_index_id, None);
         data.set_last_idx(Some(0));
         assert_eq!(data.last_index_id, Some(1));
-        assert_eq!(data.is_finished(), false);
+        assert!(!data.is_finished());
         assert_eq!(data.iter_next().count(), 1);
         data.set_last_idx(Some(0));
         assert_eq!(data.last_index_id, Some(2));
-        assert_eq!(data.is_finished(), true);
+        assert!(data.is_finished());
         assert_eq!(data.iter_next().count(), 0);
     }
 }
@@ -0,0 +1,236 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    hash::{Hash, Hasher},
+};
+
+use bevy::{
+    ecs::component::Component,
+    prelude::*,
+    reflect::{serde::ReflectSerializer, TypeRegistry, TypeRegistryInternal},
+};
+
+use crate::{data::FuzzData, fuzz_input::FuzzInput, runner::run_iteration};
+
+type HashComponent = fn(&World, Entity) -> Option<u64>;
+
+/// Hash of each component type over all entities, after one frame
+type FrameHashes = BTreeMap<String, u64>;
+
+/// Components that are compared between runs by `check_determinism`
+///
+/// Components registered in the app's type registry with `#[reflect(Component)]` are compared
+/// without registering, if they can be serialized.
+#[derive(Default)]
+pub struct DeterminismRegistry {
+    components: Vec<(&'static str, HashComponent)>,
+}
+
+impl DeterminismRegistry {
+    pub fn hash_component<C: Component + Hash>(&mut self) -> &mut Self {
+        self.components
+            .push((std::any::type_name::<C>(), hash_component::<C>));
+        self
+    }
+}
+
+fn hash_component<C: Component + Hash>(world: &World, entity: Entity) -> Option<u64> {
+    world.get::<C>(entity).map(hash_of)
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// First frame at which a run diverged from the first run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub run: usize,
+    pub frame: usize,
+    /// Components whose state differs, empty if the runs had a different number of frames
+    pub components: Vec<String>,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.components.is_empty() {
+            write!(
+                f,
+                "run {} ended at a different frame than run 0 (frame {})",
+                self.run, self.frame
+            )
+        } else {
+            write!(
+                f,
+                "run {} diverged from run 0 at frame {}, in components: {}",
+                self.run,
+                self.frame,
+                self.components.join(", ")
+            )
+        }
+    }
+}
+
+/// Applies the inputs `runs` times, comparing the world state after every frame
+///
+/// Returns the number of frames per run, or the first frame where a run diverged from the
+/// first run.
+pub fn check_determinism(
+    app: &mut App,
+    registry: &DeterminismRegistry,
+    inputs: &[FuzzInput],
+    runs: usize,
+) -> Result<usize, Divergence> {
+    let mut first_run: Option<Vec<FrameHashes>> = None;
+
+    for run in 0..runs {
+        app.insert_resource(FuzzData::new(inputs.to_vec()));
+
+        let mut frames = Vec::new();
+        run_iteration(app, |app, _| frames.push(hash_world(&app.world, registry)));
+
+        match &first_run {
+            None => first_run = Some(frames),
+            Some(first_run) => {
+                if let Some((frame, components)) = compare_runs(first_run, &frames) {
+                    return Err(Divergence {
+                        run,
+                        frame,
+                        components,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(first_run.map_or(0, |frames| frames.len()))
+}
+
+/// Returns the first differing frame, and the components that differ in it
+fn compare_runs(a: &[FrameHashes], b: &[FrameHashes]) -> Option<(usize, Vec<String>)> {
+    for frame in 0..a.len().max(b.len()) {
+        let (a, b) = match (a.get(frame), b.get(frame)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Some((frame, Vec::new())),
+        };
+
+        let components = a
+            .keys()
+            .chain(b.keys().filter(|name| !a.contains_key(*name)))
+            .filter(|name| a.get(*name) != b.get(*name))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !components.is_empty() {
+            return Some((frame, components));
+        }
+    }
+
+    None
+}
+
+fn hash_world(world: &World, registry: &DeterminismRegistry) -> FrameHashes {
+    let mut entities = world
+        .archetypes()
+        .iter()
+        .flat_map(|archetype| archetype.entities().iter().copied())
+        .collect::<Vec<_>>();
+    entities.sort_by_key(|entity| entity.to_bits());
+
+    let mut hashes = FrameHashes::new();
+
+    if let Some(types) = world.get_resource::<TypeRegistry>() {
+        let types = types.read();
+        for registration in types.iter() {
+            if let Some(reflect_component) = registration.data::<ReflectComponent>() {
+                let hash = |entity| {
+                    let value = reflect_component.reflect_component(world, entity)?;
+                    hash_reflect(value, &types)
+                };
+                insert_hash(&mut hashes, registration.name(), &entities, hash);
+            }
+        }
+    }
+
+    for (name, hash) in &registry.components {
+        insert_hash(&mut hashes, name, &entities, |entity| hash(world, entity));
+    }
+
+    hashes
+}
+
+/// Reflected values are hashed through their serialization, `Reflect::reflect_hash` is not
+/// implemented for most types
+fn hash_reflect(value: &dyn Reflect, types: &TypeRegistryInternal) -> Option<u64> {
+    ron::to_string(&ReflectSerializer::new(value, types))
+        .ok()
+        .map(hash_of)
+}
+
+fn insert_hash(
+    hashes: &mut FrameHashes,
+    name: &str,
+    entities: &[Entity],
+    hash: impl Fn(Entity) -> Option<u64>,
+) {
+    let mut hasher = DefaultHasher::new();
+    let mut found = false;
+
+    for entity in entities {
+        if let Some(component_hash) = hash(*entity) {
+            entity.to_bits().hash(&mut hasher);
+            component_hash.hash(&mut hasher);
+            found = true;
+        }
+    }
+
+    if found {
+        hashes.insert(name.to_string(), hasher.finish());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(hashes: &[(&str, u64)]) -> FrameHashes {
+        hashes
+            .iter()
+            .map(|(name, hash)| (name.to_string(), *hash))
+            .collect()
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let a = vec![frame(&[("A", 1), ("B", 2)]), frame(&[("A", 1), ("B", 3)])];
+
+        assert_eq!(compare_runs(&a, &a), None);
+
+        let b = vec![frame(&[("A", 1), ("B", 2)]), frame(&[("A", 2), ("C", 3)])];
+        assert_eq!(
+            compare_runs(&a, &b),
+            Some((1, vec!["A".to_string(), "B".to_string(), "C".to_string()]))
+        );
+
+        assert_eq!(compare_runs(&a, &a[..1]), Some((1, vec![])));
+    }
+
+    #[derive(Component, Hash)]
+    struct Cell(i32, i32);
+
+    #[test]
+    fn test_hash_world() {
+        let mut registry = DeterminismRegistry::default();
+        registry.hash_component::<Cell>();
+
+        let mut world = World::new();
+        world.spawn().insert(Cell(1, 2));
+        let before = hash_world(&world, &registry);
+        assert_eq!(before.len(), 1);
+        assert_eq!(before, hash_world(&world, &registry));
+
+        world.spawn().insert(Cell(3, 4));
+        assert_ne!(before, hash_world(&world, &registry));
+    }
+}
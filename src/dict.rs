@@ -0,0 +1,207 @@
+use std::fmt::Write;
+
+use crate::{
+    fuzz_input::FuzzInput,
+    input::{
+        gamepad::{WrapGamepad, WrapGamepadButtonType, WrapGamepadEvent, WrapGamepadEventType},
+        keyboard::{WrapKeyCode, WrapKeyboardInput},
+        mouse::{WrapMouseButton, WrapMouseButtonInput, WrapMouseScrollUnit, WrapMouseWheel},
+        touch::{WrapTouchInput, WrapTouchPhase},
+        WrapElementState,
+    },
+    math::{WrapIVec2, WrapVec2},
+    mutator::INTERESTING_COORDINATES,
+    window::{
+        event::{
+            WrapCursorEntered, WrapCursorLeft, WrapCursorMoved, WrapFileDragAndDrop,
+            WrapReceivedCharacter, WrapWindowBackendScaleFactorChanged, WrapWindowCloseRequested,
+            WrapWindowFocused, WrapWindowMoved, WrapWindowResized, WrapWindowScaleFactorChanged,
+        },
+        WrapFilePath, WrapWindowId,
+    },
+};
+
+/// Builds a libFuzzer dictionary (for `-dict=`) of encoded input events
+///
+/// Contains a COBS frame for an example of every `FuzzInput` variant, for every key code and
+/// mouse button (pressed and released), and for cursor movement to common coordinates.
+pub fn fuzz_dictionary() -> String {
+    let mut entries = Vec::new();
+
+    for input in variant_examples() {
+        let variant = format!("{:?}", input);
+        let variant = variant.split(['(', ' ']).next().unwrap_or_default();
+        entries.push((format!("event_{}", variant), input));
+    }
+
+    let states = [
+        ("pressed", WrapElementState::Pressed),
+        ("released", WrapElementState::Released),
+    ];
+
+    for key_code in (0..).map_while(WrapKeyCode::from_index) {
+        for (state_name, state) in &states {
+            let input = FuzzInput::KeyboardInput(WrapKeyboardInput {
+                scan_code: 0,
+                key_code: Some(key_code),
+                state: state.clone(),
+            });
+            entries.push((format!("key_{:?}_{}", key_code, state_name), input));
+        }
+    }
+
+    for button in [
+        WrapMouseButton::Left,
+        WrapMouseButton::Right,
+        WrapMouseButton::Middle,
+    ] {
+        for (state_name, state) in &states {
+            let input = FuzzInput::MouseButton(WrapMouseButtonInput {
+                button: button.clone(),
+                state: state.clone(),
+            });
+            entries.push((format!("mouse_{:?}_{}", button, state_name), input));
+        }
+    }
+
+    for (idx, coordinate) in INTERESTING_COORDINATES.iter().enumerate() {
+        let position = WrapVec2 {
+            x: *coordinate,
+            y: *coordinate,
+        };
+        let input = FuzzInput::CursorMoved(WrapCursorMoved {
+            id: WrapWindowId::primary(),
+            position: position.clone(),
+        });
+        entries.push((format!("cursor_moved_{}", idx), input));
+        entries.push((
+            format!("mouse_motion_{}", idx),
+            FuzzInput::MouseMotion(position),
+        ));
+    }
+
+    let mut dictionary = String::new();
+    for (name, input) in entries {
+        let frame = postcard::to_stdvec_cobs(&input).unwrap();
+        writeln!(dictionary, "# {:?}", input).unwrap();
+        writeln!(dictionary, "{}=\"{}\"", name, escape(&frame)).unwrap();
+    }
+
+    dictionary
+}
+
+/// An example of every `FuzzInput` variant (but `Reserved`), in the order of the encoding
+pub(crate) fn variant_examples() -> Vec<FuzzInput> {
+    let id = WrapWindowId::primary;
+
+    vec![
+        FuzzInput::MouseButton(WrapMouseButtonInput {
+            button: WrapMouseButton::Left,
+            state: WrapElementState::Pressed,
+        }),
+        FuzzInput::MouseWheel(WrapMouseWheel {
+            unit: WrapMouseScrollUnit::Line,
+            x: 0.0,
+            y: 1.0,
+        }),
+        FuzzInput::MouseMotion(WrapVec2 { x: 1.0, y: 1.0 }),
+        FuzzInput::CursorMoved(WrapCursorMoved {
+            id: id(),
+            position: WrapVec2 { x: 100.0, y: 100.0 },
+        }),
+        FuzzInput::WindowResized(WrapWindowResized {
+            id: id(),
+            width: 1280.0,
+            height: 720.0,
+        }),
+        FuzzInput::RunFrame,
+        FuzzInput::GamepadEvent(WrapGamepadEvent {
+            gamepad: WrapGamepad(0),
+            event_type: WrapGamepadEventType::ButtonChanged(WrapGamepadButtonType::South, 1.0),
+        }),
+        FuzzInput::Touch(WrapTouchInput {
+            phase: WrapTouchPhase::Started,
+            position: WrapVec2 { x: 100.0, y: 100.0 },
+            force: None,
+            id: 0,
+        }),
+        FuzzInput::ReceivedCharacter(WrapReceivedCharacter {
+            id: id(),
+            char: 'a',
+        }),
+        FuzzInput::KeyboardInput(WrapKeyboardInput {
+            scan_code: 0,
+            key_code: Some(WrapKeyCode::A),
+            state: WrapElementState::Pressed,
+        }),
+        FuzzInput::WindowFocused(WrapWindowFocused {
+            id: id(),
+            focused: true,
+        }),
+        FuzzInput::WindowMoved(WrapWindowMoved {
+            id: id(),
+            position: WrapIVec2 { x: 0, y: 0 },
+        }),
+        FuzzInput::WindowScaleFactorChanged(WrapWindowScaleFactorChanged {
+            id: id(),
+            scale_factor: 2.0,
+        }),
+        FuzzInput::WindowBackendScaleFactorChanged(WrapWindowBackendScaleFactorChanged {
+            id: id(),
+            scale_factor: 2.0,
+        }),
+        FuzzInput::CursorEntered(WrapCursorEntered { id: id() }),
+        FuzzInput::CursorLeft(WrapCursorLeft { id: id() }),
+        FuzzInput::WindowCloseRequested(WrapWindowCloseRequested { id: id() }),
+        FuzzInput::FileDragAndDrop(WrapFileDragAndDrop::DroppedFile {
+            id: id(),
+            path: WrapFilePath::Candidate(0),
+        }),
+        FuzzInput::RunFrameWithDelta(1.0 / 60.0),
+    ]
+}
+
+/// Escapes bytes for a libFuzzer dictionary string
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+
+    for byte in bytes {
+        match byte {
+            b'\\' | b'"' => write!(escaped, "\\{}", *byte as char).unwrap(),
+            0x20..=0x7e => escaped.push(*byte as char),
+            _ => write!(escaped, "\\x{:02X}", byte).unwrap(),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variant_examples() {
+        // one example per variant, ordered by the variant index in the encoding
+        let indices = variant_examples()
+            .iter()
+            .map(|input| postcard::to_stdvec(input).unwrap()[0] as usize)
+            .collect::<Vec<_>>();
+        let reserved = postcard::to_stdvec(&FuzzInput::Reserved).unwrap()[0] as usize;
+        assert_eq!(indices.len(), 19);
+        assert!(indices
+            .iter()
+            .enumerate()
+            .all(|(idx, index)| { *index == if idx < reserved { idx } else { idx + 1 } }));
+    }
+
+    #[test]
+    fn test_fuzz_dictionary() {
+        let dictionary = fuzz_dictionary();
+
+        assert!(dictionary.contains("event_RunFrame=\"\\x02\\x06\\x00\"\n"));
+        assert!(dictionary.contains("key_Z_pressed="));
+        assert!(dictionary.contains("mouse_Right_released="));
+        assert_eq!(escape(b"a\"\\\x01"), "a\\\"\\\\\\x01");
+    }
+}
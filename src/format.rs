@@ -0,0 +1,175 @@
+use std::io::Write;
+
+/// Magic bytes at the start of every recording file
+pub const MAGIC: &[u8; 8] = b"BEVYFUZZ";
+
+/// Version of the `FuzzInput` encoding, bumped whenever existing variants change
+///
+/// Version 0 is the implicit version of headerless recordings
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Version of bevy that the `Wrap*` types mirror
+pub const BEVY_VERSION: &str = "0.6";
+
+/// Header of a recording file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    pub format_version: u16,
+    pub bevy_version: String,
+}
+
+impl Header {
+    pub fn current() -> Self {
+        Header {
+            format_version: FORMAT_VERSION,
+            bevy_version: BEVY_VERSION.to_string(),
+        }
+    }
+
+    /// Header layout: `MAGIC | format_version: u16 le | bevy_version length: u8 | bevy_version`
+    pub fn write(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.format_version.to_le_bytes())?;
+        writer.write_all(&[self.bevy_version.len() as u8])?;
+        writer.write_all(self.bevy_version.as_bytes())
+    }
+
+    /// Reads the header from the start of the input
+    ///
+    /// Returns `Ok(None)` if the input does not start with the magic bytes (a headerless input),
+    /// otherwise the header and its length in bytes.
+    pub fn read(input: &[u8]) -> Result<Option<(Header, usize)>, ParseError> {
+        if !input.starts_with(MAGIC) {
+            return Ok(None);
+        }
+
+        let rest = &input[MAGIC.len()..];
+        if rest.len() < 3 {
+            return Err(ParseError::TruncatedHeader);
+        }
+
+        let format_version = u16::from_le_bytes([rest[0], rest[1]]);
+        let bevy_version_len = rest[2] as usize;
+        let bevy_version = rest
+            .get(3..3 + bevy_version_len)
+            .ok_or(ParseError::TruncatedHeader)?;
+        let bevy_version = String::from_utf8_lossy(bevy_version).into_owned();
+
+        let header = Header {
+            format_version,
+            bevy_version,
+        };
+
+        Ok(Some((header, MAGIC.len() + 3 + bevy_version_len)))
+    }
+
+    /// Checks that the recording can be decoded with the current `FuzzInput` encoding
+    pub fn validate(&self) -> Result<(), ParseError> {
+        if self.format_version > FORMAT_VERSION {
+            return Err(ParseError::UnsupportedFormatVersion {
+                found: self.format_version,
+                supported: FORMAT_VERSION,
+            });
+        }
+
+        if self.bevy_version != BEVY_VERSION {
+            return Err(ParseError::BevyVersionMismatch {
+                found: self.bevy_version.clone(),
+                expected: BEVY_VERSION,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Error while parsing a recording
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The input starts with the magic bytes, but the header is cut short
+    TruncatedHeader,
+    /// The recording was written by a newer bevy_fuzz
+    UnsupportedFormatVersion { found: u16, supported: u16 },
+    /// The recording was written against another bevy version
+    BevyVersionMismatch {
+        found: String,
+        expected: &'static str,
+    },
+    /// A COBS frame at the byte offset could not be decoded into a `FuzzInput`
+    InvalidFrame { offset: usize },
+    /// A text recording could not be parsed
+    InvalidText(String),
+    /// A line of an input script could not be compiled
+    InvalidScript { line: usize, message: String },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::TruncatedHeader => write!(f, "recording header is truncated"),
+            ParseError::UnsupportedFormatVersion { found, supported } => write!(
+                f,
+                "recording format version {} is newer than the supported version {}",
+                found, supported
+            ),
+            ParseError::BevyVersionMismatch { found, expected } => write!(
+                f,
+                "recording was made with bevy {}, but this build uses bevy {}",
+                found, expected
+            ),
+            ParseError::InvalidFrame { offset } => {
+                write!(f, "invalid input frame at byte offset {}", offset)
+            }
+            ParseError::InvalidText(e) => write!(f, "invalid text recording: {}", e),
+            ParseError::InvalidScript { line, message } => {
+                write!(f, "invalid script on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let mut data = Vec::new();
+        Header::current().write(&mut data).unwrap();
+        data.extend([1, 2, 3]);
+
+        let (header, header_len) = Header::read(&data).unwrap().unwrap();
+        assert_eq!(header, Header::current());
+        assert_eq!(&data[header_len..], &[1, 2, 3]);
+        assert_eq!(header.validate(), Ok(()));
+
+        assert_eq!(Header::read(&[2, 3, 1, 0]), Ok(None));
+        assert_eq!(
+            Header::read(&data[..MAGIC.len() + 4]),
+            Err(ParseError::TruncatedHeader)
+        );
+
+        let newer = Header {
+            format_version: FORMAT_VERSION + 1,
+            bevy_version: BEVY_VERSION.to_string(),
+        };
+        assert_eq!(
+            newer.validate(),
+            Err(ParseError::UnsupportedFormatVersion {
+                found: FORMAT_VERSION + 1,
+                supported: FORMAT_VERSION
+            })
+        );
+
+        let other_bevy = Header {
+            format_version: FORMAT_VERSION,
+            bevy_version: "0.5".to_string(),
+        };
+        assert!(matches!(
+            other_bevy.validate(),
+            Err(ParseError::BevyVersionMismatch { .. })
+        ));
+    }
+}
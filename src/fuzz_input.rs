@@ -1,13 +1,55 @@
-use crate::input::{keyboard, mouse};
+use crate::input::{gamepad, keyboard, mouse, touch};
 use serde::{Deserialize, Serialize};
 
+/// A single fuzzed input event, or a frame boundary
+///
+/// The variant order is part of the binary encoding: new variants must be appended to the end
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum FuzzInput {
     MouseButton(mouse::WrapMouseButtonInput),
-    KeyboardInput(keyboard::WrapKeyboardInput),
+    /// Reserved for the keyboard input of format version 0 recordings (converted by `migrate`),
+    /// never generated nor applied
+    #[cfg_attr(feature = "arbitrary", arbitrary(skip))]
+    Reserved,
     MouseWheel(mouse::WrapMouseWheel),
     MouseMotion(crate::math::WrapVec2),
     CursorMoved(crate::window::event::WrapCursorMoved),
     WindowResized(crate::window::event::WrapWindowResized),
     RunFrame,
+    GamepadEvent(gamepad::WrapGamepadEvent),
+    Touch(touch::WrapTouchInput),
+    ReceivedCharacter(crate::window::event::WrapReceivedCharacter),
+    KeyboardInput(keyboard::WrapKeyboardInput),
+    WindowFocused(crate::window::event::WrapWindowFocused),
+    WindowMoved(crate::window::event::WrapWindowMoved),
+    WindowScaleFactorChanged(crate::window::event::WrapWindowScaleFactorChanged),
+    WindowBackendScaleFactorChanged(crate::window::event::WrapWindowBackendScaleFactorChanged),
+    CursorEntered(crate::window::event::WrapCursorEntered),
+    CursorLeft(crate::window::event::WrapCursorLeft),
+    WindowCloseRequested(crate::window::event::WrapWindowCloseRequested),
+    FileDragAndDrop(crate::window::event::WrapFileDragAndDrop),
+    /// Frame boundary, with the simulated time (in seconds) since the previous frame
+    RunFrameWithDelta(f32),
+}
+
+impl FuzzInput {
+    /// Whether the input ends a frame (`RunFrame` or `RunFrameWithDelta`)
+    pub fn is_frame_end(&self) -> bool {
+        matches!(self, FuzzInput::RunFrame | FuzzInput::RunFrameWithDelta(_))
+    }
+
+    /// The x and y coordinates (or width and height) of the input, if it has any
+    pub(crate) fn coordinates_mut(&mut self) -> Option<(&mut f32, &mut f32)> {
+        match self {
+            FuzzInput::MouseWheel(wheel) => Some((&mut wheel.x, &mut wheel.y)),
+            FuzzInput::MouseMotion(motion) => Some((&mut motion.x, &mut motion.y)),
+            FuzzInput::CursorMoved(cursor) => {
+                Some((&mut cursor.position.x, &mut cursor.position.y))
+            }
+            FuzzInput::WindowResized(resized) => Some((&mut resized.width, &mut resized.height)),
+            FuzzInput::Touch(touch) => Some((&mut touch.position.x, &mut touch.position.y)),
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,200 @@
+use bevy::input::gamepad::{
+    Gamepad, GamepadAxisType, GamepadButtonType, GamepadEventRaw, GamepadEventType,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapGamepadEvent {
+    pub gamepad: WrapGamepad,
+    pub event_type: WrapGamepadEventType,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapGamepad(pub usize);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum WrapGamepadEventType {
+    Connected,
+    Disconnected,
+    ButtonChanged(WrapGamepadButtonType, f32),
+    AxisChanged(WrapGamepadAxisType, f32),
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum WrapGamepadButtonType {
+    South,
+    East,
+    North,
+    West,
+    C,
+    Z,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum WrapGamepadAxisType {
+    LeftStickX,
+    LeftStickY,
+    LeftZ,
+    RightStickX,
+    RightStickY,
+    RightZ,
+    DPadX,
+    DPadY,
+}
+
+impl From<&WrapGamepadEvent> for GamepadEventRaw {
+    fn from(inp: &WrapGamepadEvent) -> Self {
+        GamepadEventRaw((&inp.gamepad).into(), (&inp.event_type).into())
+    }
+}
+
+impl From<&GamepadEventRaw> for WrapGamepadEvent {
+    fn from(inp: &GamepadEventRaw) -> Self {
+        WrapGamepadEvent {
+            gamepad: (&inp.0).into(),
+            event_type: (&inp.1).into(),
+        }
+    }
+}
+
+impl From<&WrapGamepad> for Gamepad {
+    fn from(inp: &WrapGamepad) -> Self {
+        Gamepad(inp.0)
+    }
+}
+
+impl From<&Gamepad> for WrapGamepad {
+    fn from(inp: &Gamepad) -> Self {
+        WrapGamepad(inp.0)
+    }
+}
+
+impl From<&WrapGamepadEventType> for GamepadEventType {
+    fn from(inp: &WrapGamepadEventType) -> Self {
+        match inp {
+            WrapGamepadEventType::Connected => GamepadEventType::Connected,
+            WrapGamepadEventType::Disconnected => GamepadEventType::Disconnected,
+            WrapGamepadEventType::ButtonChanged(button_type, value) => {
+                GamepadEventType::ButtonChanged(button_type.into(), *value)
+            }
+            WrapGamepadEventType::AxisChanged(axis_type, value) => {
+                GamepadEventType::AxisChanged(axis_type.into(), *value)
+            }
+        }
+    }
+}
+
+impl From<&GamepadEventType> for WrapGamepadEventType {
+    fn from(inp: &GamepadEventType) -> Self {
+        match inp {
+            GamepadEventType::Connected => WrapGamepadEventType::Connected,
+            GamepadEventType::Disconnected => WrapGamepadEventType::Disconnected,
+            GamepadEventType::ButtonChanged(button_type, value) => {
+                WrapGamepadEventType::ButtonChanged(button_type.into(), *value)
+            }
+            GamepadEventType::AxisChanged(axis_type, value) => {
+                WrapGamepadEventType::AxisChanged(axis_type.into(), *value)
+            }
+        }
+    }
+}
+
+impl From<&WrapGamepadButtonType> for GamepadButtonType {
+    fn from(inp: &WrapGamepadButtonType) -> Self {
+        match inp {
+            WrapGamepadButtonType::South => GamepadButtonType::South,
+            WrapGamepadButtonType::East => GamepadButtonType::East,
+            WrapGamepadButtonType::North => GamepadButtonType::North,
+            WrapGamepadButtonType::West => GamepadButtonType::West,
+            WrapGamepadButtonType::C => GamepadButtonType::C,
+            WrapGamepadButtonType::Z => GamepadButtonType::Z,
+            WrapGamepadButtonType::LeftTrigger => GamepadButtonType::LeftTrigger,
+            WrapGamepadButtonType::LeftTrigger2 => GamepadButtonType::LeftTrigger2,
+            WrapGamepadButtonType::RightTrigger => GamepadButtonType::RightTrigger,
+            WrapGamepadButtonType::RightTrigger2 => GamepadButtonType::RightTrigger2,
+            WrapGamepadButtonType::Select => GamepadButtonType::Select,
+            WrapGamepadButtonType::Start => GamepadButtonType::Start,
+            WrapGamepadButtonType::Mode => GamepadButtonType::Mode,
+            WrapGamepadButtonType::LeftThumb => GamepadButtonType::LeftThumb,
+            WrapGamepadButtonType::RightThumb => GamepadButtonType::RightThumb,
+            WrapGamepadButtonType::DPadUp => GamepadButtonType::DPadUp,
+            WrapGamepadButtonType::DPadDown => GamepadButtonType::DPadDown,
+            WrapGamepadButtonType::DPadLeft => GamepadButtonType::DPadLeft,
+            WrapGamepadButtonType::DPadRight => GamepadButtonType::DPadRight,
+        }
+    }
+}
+
+impl From<&GamepadButtonType> for WrapGamepadButtonType {
+    fn from(inp: &GamepadButtonType) -> Self {
+        match inp {
+            GamepadButtonType::South => WrapGamepadButtonType::South,
+            GamepadButtonType::East => WrapGamepadButtonType::East,
+            GamepadButtonType::North => WrapGamepadButtonType::North,
+            GamepadButtonType::West => WrapGamepadButtonType::West,
+            GamepadButtonType::C => WrapGamepadButtonType::C,
+            GamepadButtonType::Z => WrapGamepadButtonType::Z,
+            GamepadButtonType::LeftTrigger => WrapGamepadButtonType::LeftTrigger,
+            GamepadButtonType::LeftTrigger2 => WrapGamepadButtonType::LeftTrigger2,
+            GamepadButtonType::RightTrigger => WrapGamepadButtonType::RightTrigger,
+            GamepadButtonType::RightTrigger2 => WrapGamepadButtonType::RightTrigger2,
+            GamepadButtonType::Select => WrapGamepadButtonType::Select,
+            GamepadButtonType::Start => WrapGamepadButtonType::Start,
+            GamepadButtonType::Mode => WrapGamepadButtonType::Mode,
+            GamepadButtonType::LeftThumb => WrapGamepadButtonType::LeftThumb,
+            GamepadButtonType::RightThumb => WrapGamepadButtonType::RightThumb,
+            GamepadButtonType::DPadUp => WrapGamepadButtonType::DPadUp,
+            GamepadButtonType::DPadDown => WrapGamepadButtonType::DPadDown,
+            GamepadButtonType::DPadLeft => WrapGamepadButtonType::DPadLeft,
+            GamepadButtonType::DPadRight => WrapGamepadButtonType::DPadRight,
+        }
+    }
+}
+
+impl From<&WrapGamepadAxisType> for GamepadAxisType {
+    fn from(inp: &WrapGamepadAxisType) -> Self {
+        match inp {
+            WrapGamepadAxisType::LeftStickX => GamepadAxisType::LeftStickX,
+            WrapGamepadAxisType::LeftStickY => GamepadAxisType::LeftStickY,
+            WrapGamepadAxisType::LeftZ => GamepadAxisType::LeftZ,
+            WrapGamepadAxisType::RightStickX => GamepadAxisType::RightStickX,
+            WrapGamepadAxisType::RightStickY => GamepadAxisType::RightStickY,
+            WrapGamepadAxisType::RightZ => GamepadAxisType::RightZ,
+            WrapGamepadAxisType::DPadX => GamepadAxisType::DPadX,
+            WrapGamepadAxisType::DPadY => GamepadAxisType::DPadY,
+        }
+    }
+}
+
+impl From<&GamepadAxisType> for WrapGamepadAxisType {
+    fn from(inp: &GamepadAxisType) -> Self {
+        match inp {
+            GamepadAxisType::LeftStickX => WrapGamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY => WrapGamepadAxisType::LeftStickY,
+            GamepadAxisType::LeftZ => WrapGamepadAxisType::LeftZ,
+            GamepadAxisType::RightStickX => WrapGamepadAxisType::RightStickX,
+            GamepadAxisType::RightStickY => WrapGamepadAxisType::RightStickY,
+            GamepadAxisType::RightZ => WrapGamepadAxisType::RightZ,
+            GamepadAxisType::DPadX => WrapGamepadAxisType::DPadX,
+            GamepadAxisType::DPadY => WrapGamepadAxisType::DPadY,
+        }
+    }
+}
@@ -3,13 +3,15 @@ use bevy::{input::keyboard::KeyboardInput, prelude::KeyCode};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct WrapKeyboardInput {
-    //pub scan_code: u32, FIXME
+    pub scan_code: u32,
     pub key_code: Option<WrapKeyCode>,
     pub state: WrapElementState,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum WrapKeyCode {
     Key1,
     Key2,
@@ -187,10 +189,17 @@ pub enum WrapKeyCode {
     Cut,
 }
 
+impl WrapKeyCode {
+    /// Returns the key code with the given variant index in the binary encoding
+    pub(crate) fn from_index(index: u32) -> Option<Self> {
+        postcard::from_bytes(&postcard::to_stdvec(&index).ok()?).ok()
+    }
+}
+
 impl From<&WrapKeyboardInput> for KeyboardInput {
     fn from(inp: &WrapKeyboardInput) -> Self {
         KeyboardInput {
-            scan_code: 0, // FIXME
+            scan_code: inp.scan_code,
             key_code: inp.key_code.map(|v| (&v).into()),
             state: (&inp.state).into(),
         }
@@ -200,7 +209,7 @@ impl From<&WrapKeyboardInput> for KeyboardInput {
 impl From<&KeyboardInput> for WrapKeyboardInput {
     fn from(inp: &KeyboardInput) -> Self {
         WrapKeyboardInput {
-            // scan_code: 0, // FIXME
+            scan_code: inp.scan_code,
             key_code: inp.key_code.map(|v| (&v).into()),
             state: (&inp.state).into(),
         }
@@ -1,10 +1,13 @@
 use bevy::input::ElementState;
 use serde::{Deserialize, Serialize};
 
+pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+pub mod touch;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum WrapElementState {
     Pressed,
     Released,
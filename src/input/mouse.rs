@@ -6,12 +6,14 @@ use bevy::{
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct WrapMouseButtonInput {
     pub button: WrapMouseButton,
     pub state: WrapElementState,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum WrapMouseButton {
     Left,
     Right,
@@ -20,12 +22,14 @@ pub enum WrapMouseButton {
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum WrapMouseScrollUnit {
     Line,
     Pixel,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct WrapMouseWheel {
     pub unit: WrapMouseScrollUnit,
     pub x: f32,
@@ -0,0 +1,112 @@
+use bevy::input::touch::{ForceTouch, TouchInput, TouchPhase};
+use serde::{Deserialize, Serialize};
+
+use crate::math::WrapVec2;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapTouchInput {
+    pub phase: WrapTouchPhase,
+    pub position: WrapVec2,
+    pub force: Option<WrapForceTouch>,
+    /// Finger id
+    pub id: u64,
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum WrapTouchPhase {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum WrapForceTouch {
+    Calibrated {
+        force: f64,
+        max_possible_force: f64,
+        altitude_angle: Option<f64>,
+    },
+    Normalized(f64),
+}
+
+impl From<&WrapTouchInput> for TouchInput {
+    fn from(inp: &WrapTouchInput) -> Self {
+        TouchInput {
+            phase: (&inp.phase).into(),
+            position: (&inp.position).into(),
+            force: inp.force.as_ref().map(|v| v.into()),
+            id: inp.id,
+        }
+    }
+}
+
+impl From<&TouchInput> for WrapTouchInput {
+    fn from(inp: &TouchInput) -> Self {
+        WrapTouchInput {
+            phase: (&inp.phase).into(),
+            position: (&inp.position).into(),
+            force: inp.force.as_ref().map(|v| v.into()),
+            id: inp.id,
+        }
+    }
+}
+
+impl From<&WrapTouchPhase> for TouchPhase {
+    fn from(inp: &WrapTouchPhase) -> Self {
+        match inp {
+            WrapTouchPhase::Started => TouchPhase::Started,
+            WrapTouchPhase::Moved => TouchPhase::Moved,
+            WrapTouchPhase::Ended => TouchPhase::Ended,
+            WrapTouchPhase::Cancelled => TouchPhase::Cancelled,
+        }
+    }
+}
+
+impl From<&TouchPhase> for WrapTouchPhase {
+    fn from(inp: &TouchPhase) -> Self {
+        match inp {
+            TouchPhase::Started => WrapTouchPhase::Started,
+            TouchPhase::Moved => WrapTouchPhase::Moved,
+            TouchPhase::Ended => WrapTouchPhase::Ended,
+            TouchPhase::Cancelled => WrapTouchPhase::Cancelled,
+        }
+    }
+}
+
+impl From<&WrapForceTouch> for ForceTouch {
+    fn from(inp: &WrapForceTouch) -> Self {
+        match inp {
+            WrapForceTouch::Calibrated {
+                force,
+                max_possible_force,
+                altitude_angle,
+            } => ForceTouch::Calibrated {
+                force: *force,
+                max_possible_force: *max_possible_force,
+                altitude_angle: *altitude_angle,
+            },
+            WrapForceTouch::Normalized(v) => ForceTouch::Normalized(*v),
+        }
+    }
+}
+
+impl From<&ForceTouch> for WrapForceTouch {
+    fn from(inp: &ForceTouch) -> Self {
+        match inp {
+            ForceTouch::Calibrated {
+                force,
+                max_possible_force,
+                altitude_angle,
+            } => WrapForceTouch::Calibrated {
+                force: *force,
+                max_possible_force: *max_possible_force,
+                altitude_angle: *altitude_angle,
+            },
+            ForceTouch::Normalized(v) => WrapForceTouch::Normalized(*v),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::{ecs::system::BoxedSystem, prelude::*};
+
+/// Result of an invariant check, the error describes the violation
+pub type InvariantResult = Result<(), String>;
+
+struct Invariant {
+    name: &'static str,
+    system: BoxedSystem<(), InvariantResult>,
+}
+
+/// Invariant systems, checked by the fuzz runner after every tick
+///
+/// An invariant is a system returning `InvariantResult`. A violation panics, so that the fuzzer
+/// records the input as a crash.
+#[derive(Default)]
+pub struct InvariantRegistry {
+    invariants: Vec<Invariant>,
+}
+
+impl InvariantRegistry {
+    pub fn add<Params>(
+        &mut self,
+        name: &'static str,
+        system: impl IntoSystem<(), InvariantResult, Params>,
+    ) -> &mut Self {
+        self.invariants.push(Invariant {
+            name,
+            system: Box::new(system.system()),
+        });
+        self
+    }
+
+    pub(crate) fn initialize(&mut self, world: &mut World) {
+        for invariant in &mut self.invariants {
+            invariant.system.initialize(world);
+        }
+    }
+
+    /// Runs the invariant systems, returning the name and error of the first violation
+    pub(crate) fn check(&mut self, world: &mut World) -> Option<(&'static str, String)> {
+        for invariant in &mut self.invariants {
+            let result = invariant.system.run((), world);
+            invariant.system.apply_buffers(world);
+
+            if let Err(message) = result {
+                return Some((invariant.name, message));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Health(i32);
+
+    fn health_not_negative(health: Res<Health>) -> InvariantResult {
+        if health.0 < 0 {
+            return Err(format!("health is {}", health.0));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_invariants() {
+        let mut world = World::new();
+        world.insert_resource(Health(1));
+
+        let mut invariants = InvariantRegistry::default();
+        invariants.add("health not negative", health_not_negative);
+        invariants.initialize(&mut world);
+        assert_eq!(invariants.check(&mut world), None);
+
+        world.insert_resource(Health(-2));
+        assert_eq!(
+            invariants.check(&mut world),
+            Some(("health not negative", String::from("health is -2")))
+        );
+    }
+}
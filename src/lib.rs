@@ -2,21 +2,46 @@ use bevy::prelude::*;
 
 mod bootstrap;
 mod data;
+mod determinism;
+mod dict;
+mod format;
 mod fuzz_input;
 mod input;
+mod invariant;
 mod math;
+mod migrate;
+mod minimize;
+mod mutator;
 mod output;
+mod progress;
 mod runner;
+mod script;
+mod snapshot;
+mod text;
+mod time;
 mod window;
 
 pub use bootstrap::FuzzTarget;
-pub use bootstrap::{bin_bootstrap, fuzz_bootstrap};
-pub use output::{parse_commands, EventOutputPlugin};
-pub use runner::fuzz_runner;
+pub use bootstrap::{bin_bootstrap, fuzz_bootstrap, fuzz_bootstrap_inputs};
+pub use determinism::{check_determinism, DeterminismRegistry, Divergence};
+pub use dict::fuzz_dictionary;
+pub use format::{Header, ParseError};
+pub use invariant::{InvariantRegistry, InvariantResult};
+pub use migrate::migrate;
+pub use minimize::minimize;
+pub use mutator::{crossover, mutate};
+pub use output::{
+    encode_commands, parse_commands, parse_commands_lenient, parse_frames, EventOutputPlugin,
+};
+pub use runner::{fuzz_runner, FuzzRunnerSettings};
+pub use script::compile_script;
+pub use snapshot::{SnapshotRegistry, WorldSnapshot};
+pub use text::{export_text, import_text, parse_input_file};
+pub use time::{FuzzClock, FuzzTime};
 
 pub mod prelude {
     pub use crate::{
-        bootstrap::{bin_bootstrap, fuzz_bootstrap, FuzzTarget},
+        bootstrap::{bin_bootstrap, fuzz_bootstrap, fuzz_bootstrap_inputs, FuzzTarget},
         data::FuzzData,
         fuzz_input::FuzzInput,
         FuzzPlugin,
@@ -48,7 +73,16 @@ impl Plugin for FuzzPlugin {
     }
 }
 
-fn clean_all_system(mut commands: Commands, all_entities: Query<Entity>) {
+fn clean_all_system(
+    mut commands: Commands,
+    all_entities: Query<Entity>,
+    snapshot: Option<Res<WorldSnapshot>>,
+) {
+    // the fuzz runner has already reset the entities to the snapshot
+    if snapshot.is_some() {
+        return;
+    }
+
     for entity in all_entities.iter() {
         commands.entity(entity).despawn_recursive();
     }
@@ -1,7 +1,8 @@
-use bevy::math::Vec2;
+use bevy::math::{IVec2, Vec2};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct WrapVec2 {
     pub x: f32,
     pub y: f32,
@@ -15,10 +16,25 @@ impl From<&WrapVec2> for Vec2 {
 
 impl From<&Vec2> for WrapVec2 {
     fn from(inp: &Vec2) -> Self {
-        WrapVec2 {
-            x: inp.x,
-            y: inp.y
-        }
+        WrapVec2 { x: inp.x, y: inp.y }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapIVec2 {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl From<&WrapIVec2> for IVec2 {
+    fn from(inp: &WrapIVec2) -> Self {
+        IVec2::new(inp.x, inp.y)
+    }
+}
+
+impl From<&IVec2> for WrapIVec2 {
+    fn from(inp: &IVec2) -> Self {
+        WrapIVec2 { x: inp.x, y: inp.y }
+    }
+}
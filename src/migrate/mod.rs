@@ -0,0 +1,71 @@
+use crate::{
+    format::{Header, ParseError, FORMAT_VERSION},
+    fuzz_input::FuzzInput,
+    output::decode_frames,
+};
+
+mod v0;
+
+/// Reads a recording of any supported format version, converting it into current input events
+///
+/// Headerless inputs are treated as format version 0 recordings.
+pub fn migrate(input: Vec<u8>) -> Result<Vec<FuzzInput>, ParseError> {
+    let (inputs, _) = match Header::read(&input)? {
+        Some((header, header_len)) => {
+            header.validate()?;
+            decode_version(header.format_version, input[header_len..].to_vec(), false)?
+        }
+        None => decode_version(0, input, false)?,
+    };
+
+    Ok(inputs)
+}
+
+/// Decodes headerless frames written with the given format version
+///
+/// Returns the input events, and the number of frames that could not be decoded (see
+/// `decode_frames`).
+pub(crate) fn decode_version(
+    format_version: u16,
+    frames: Vec<u8>,
+    lenient: bool,
+) -> Result<(Vec<FuzzInput>, usize), ParseError> {
+    match format_version {
+        0 => {
+            let (inputs, dropped) = decode_frames::<v0::FuzzInput>(frames, lenient)?;
+            Ok((inputs.into_iter().map(|v| v.into()).collect(), dropped))
+        }
+        FORMAT_VERSION => decode_frames(frames, lenient),
+        _ => Err(ParseError::UnsupportedFormatVersion {
+            found: format_version,
+            supported: FORMAT_VERSION,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{
+        keyboard::{WrapKeyCode, WrapKeyboardInput},
+        WrapElementState,
+    };
+
+    #[test]
+    fn test_migrate_v0() {
+        // KeyboardInput { key_code: Some(A), state: Pressed }, RunFrame
+        let data = vec![4, 1, 1, 10, 1, 0, 2, 6, 0];
+
+        assert_eq!(
+            migrate(data).unwrap(),
+            &[
+                FuzzInput::KeyboardInput(WrapKeyboardInput {
+                    scan_code: 0,
+                    key_code: Some(WrapKeyCode::A),
+                    state: WrapElementState::Pressed,
+                }),
+                FuzzInput::RunFrame
+            ]
+        );
+    }
+}
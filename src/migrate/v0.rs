@@ -0,0 +1,51 @@
+//! Format version 0: headerless recordings, before scan codes and the non-mouse/keyboard events
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fuzz_input,
+    input::{
+        keyboard::{WrapKeyCode, WrapKeyboardInput},
+        mouse, WrapElementState,
+    },
+    math::WrapVec2,
+    window::event::{WrapCursorMoved, WrapWindowResized},
+};
+
+/// Keyboard input as recorded before scan codes were captured
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WrapLegacyKeyboardInput {
+    pub key_code: Option<WrapKeyCode>,
+    pub state: WrapElementState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FuzzInput {
+    MouseButton(mouse::WrapMouseButtonInput),
+    KeyboardInput(WrapLegacyKeyboardInput),
+    MouseWheel(mouse::WrapMouseWheel),
+    MouseMotion(WrapVec2),
+    CursorMoved(WrapCursorMoved),
+    WindowResized(WrapWindowResized),
+    RunFrame,
+}
+
+impl From<FuzzInput> for fuzz_input::FuzzInput {
+    fn from(inp: FuzzInput) -> Self {
+        match inp {
+            FuzzInput::MouseButton(v) => fuzz_input::FuzzInput::MouseButton(v),
+            FuzzInput::KeyboardInput(v) => {
+                fuzz_input::FuzzInput::KeyboardInput(WrapKeyboardInput {
+                    scan_code: 0,
+                    key_code: v.key_code,
+                    state: v.state,
+                })
+            }
+            FuzzInput::MouseWheel(v) => fuzz_input::FuzzInput::MouseWheel(v),
+            FuzzInput::MouseMotion(v) => fuzz_input::FuzzInput::MouseMotion(v),
+            FuzzInput::CursorMoved(v) => fuzz_input::FuzzInput::CursorMoved(v),
+            FuzzInput::WindowResized(v) => fuzz_input::FuzzInput::WindowResized(v),
+            FuzzInput::RunFrame => fuzz_input::FuzzInput::RunFrame,
+        }
+    }
+}
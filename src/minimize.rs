@@ -0,0 +1,308 @@
+use std::{
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+};
+
+use bevy::{ecs::schedule::SingleThreadedExecutor, prelude::*};
+
+use crate::{data::FuzzData, fuzz_input::FuzzInput, progress::RunnerProgress, runner::fuzz_runner};
+
+/// Shrinks inputs that make the app panic, keeping the panic message
+///
+/// Applies the inputs repeatedly with `fuzz_runner`, dropping frames, dropping events, merging
+/// frames and simplifying coordinates for as long as the app still panics with the same message.
+/// Returns the smallest inputs found and the panic message, or `None` if the inputs do not make
+/// the app panic. Panics are not printed while minimizing, and the stages of the app are switched
+/// to run their systems on the calling thread.
+pub fn minimize(app: &mut App, inputs: &[FuzzInput]) -> Option<(Vec<FuzzInput>, String)> {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    run_single_threaded(&mut app.schedule);
+
+    let minimized = panic_message(app, inputs).map(|message| {
+        let inputs = shrink(inputs.to_vec(), |candidate| {
+            panic_message(app, candidate).as_ref() == Some(&message)
+        });
+        (inputs, message)
+    });
+
+    panic::set_hook(previous_hook);
+    minimized
+}
+
+/// Switches all system stages (also nested ones, e.g. startup) to the single-threaded executor
+///
+/// A system panicking on a task pool thread would leave its stage waiting forever, instead of
+/// unwinding to `catch_unwind`.
+fn run_single_threaded(schedule: &mut Schedule) {
+    let labels = schedule
+        .iter_stages()
+        .map(|(label, _)| label.dyn_clone())
+        .collect::<Vec<_>>();
+
+    for label in labels {
+        if let Some(stage) = schedule.get_stage_mut::<SystemStage>(&*label) {
+            stage.set_executor(Box::new(SingleThreadedExecutor::default()));
+        } else if let Some(schedule) = schedule.get_stage_mut::<Schedule>(&*label) {
+            run_single_threaded(schedule);
+        }
+    }
+}
+
+/// Applies the inputs, returning the panic message if the app panics
+fn panic_message(app: &mut App, inputs: &[FuzzInput]) -> Option<String> {
+    app.insert_resource(FuzzData::new(inputs.to_vec()));
+
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| fuzz_runner(app))).err()?;
+
+    // the iteration did not finish, the next one starts from a reset world
+    if let Some(progress) = app.world.get_resource::<RunnerProgress>() {
+        progress.iteration_finished();
+    }
+
+    let message = match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .unwrap_or_default(),
+    };
+
+    Some(message)
+}
+
+/// Delta-debugs the inputs until none of the reductions keeps `still_fails` true
+fn shrink(
+    mut inputs: Vec<FuzzInput>,
+    mut still_fails: impl FnMut(&[FuzzInput]) -> bool,
+) -> Vec<FuzzInput> {
+    loop {
+        // drop frames, drop events, merge frames (by dropping their ends)
+        let mut shrunk = drop_chunks(&mut inputs, frames, &mut still_fails);
+        shrunk |= drop_chunks(&mut inputs, events, &mut still_fails);
+        shrunk |= drop_chunks(&mut inputs, frame_ends, &mut still_fails);
+        shrunk |= simplify_coordinates(&mut inputs, &mut still_fails);
+
+        if !shrunk {
+            return inputs;
+        }
+    }
+}
+
+fn frames(inputs: &[FuzzInput]) -> Vec<Range<usize>> {
+    let mut start = 0;
+    inputs
+        .split_inclusive(FuzzInput::is_frame_end)
+        .map(|frame| {
+            start += frame.len();
+            start - frame.len()..start
+        })
+        .collect()
+}
+
+fn events(inputs: &[FuzzInput]) -> Vec<Range<usize>> {
+    (0..inputs.len())
+        .filter(|idx| !inputs[*idx].is_frame_end())
+        .map(|idx| idx..idx + 1)
+        .collect()
+}
+
+fn frame_ends(inputs: &[FuzzInput]) -> Vec<Range<usize>> {
+    (0..inputs.len())
+        .filter(|idx| inputs[*idx].is_frame_end())
+        .map(|idx| idx..idx + 1)
+        .collect()
+}
+
+/// Drops chunks of consecutive units (halving the chunk size down to a single unit), as long as
+/// the inputs still fail without them
+fn drop_chunks(
+    inputs: &mut Vec<FuzzInput>,
+    units: fn(&[FuzzInput]) -> Vec<Range<usize>>,
+    still_fails: &mut impl FnMut(&[FuzzInput]) -> bool,
+) -> bool {
+    let mut shrunk = false;
+    let mut chunk = (units(inputs).len() / 2).max(1);
+
+    loop {
+        let mut start = 0;
+        loop {
+            let current = units(inputs);
+            if start >= current.len() {
+                break;
+            }
+
+            let end = (start + chunk).min(current.len());
+            let mut candidate = inputs.clone();
+            candidate.drain(current[start].start..current[end - 1].end);
+
+            if still_fails(&candidate) {
+                *inputs = candidate;
+                shrunk = true;
+            } else {
+                start = end;
+            }
+        }
+
+        if chunk == 1 {
+            return shrunk;
+        }
+        chunk /= 2;
+    }
+}
+
+/// Replaces coordinates with zero, or rounds them, as long as the inputs still fail
+fn simplify_coordinates(
+    inputs: &mut [FuzzInput],
+    still_fails: &mut impl FnMut(&[FuzzInput]) -> bool,
+) -> bool {
+    let mut shrunk = false;
+
+    for idx in 0..inputs.len() {
+        for axis in 0..2 {
+            let value = match inputs[idx].coordinates_mut() {
+                Some((x, y)) => *[x, y][axis],
+                None => break,
+            };
+
+            for simplified in [0.0, value.round()] {
+                // NaN is never equal, but simplifies to zero
+                if simplified == value {
+                    continue;
+                }
+
+                let mut candidate = inputs.to_vec();
+                if let Some((x, y)) = candidate[idx].coordinates_mut() {
+                    *[x, y][axis] = simplified;
+                }
+
+                if still_fails(&candidate) {
+                    inputs.clone_from_slice(&candidate);
+                    shrunk = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    shrunk
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::mouse::MouseMotion;
+
+    use super::*;
+    use crate::{math::WrapVec2, FuzzTarget, InvariantRegistry, InvariantResult, SnapshotRegistry};
+
+    #[derive(Clone)]
+    struct Distance(f32);
+
+    fn move_system(mut motions: EventReader<MouseMotion>, mut distance: ResMut<Distance>) {
+        for motion in motions.iter() {
+            distance.0 += motion.delta.x;
+        }
+    }
+
+    fn distance_not_too_far(distance: Res<Distance>) -> InvariantResult {
+        if distance.0 > 100.0 {
+            return Err(String::from("moved too far"));
+        }
+        Ok(())
+    }
+
+    struct MovingApp;
+
+    impl Plugin for MovingApp {
+        fn build(&self, app: &mut App) {
+            app.insert_resource(Distance(0.0)).add_system(move_system);
+        }
+    }
+
+    impl FuzzTarget for MovingApp {
+        fn snapshot(&self, registry: &mut SnapshotRegistry) {
+            registry.clone_resource::<Distance>();
+        }
+
+        fn invariants(&self, registry: &mut InvariantRegistry) {
+            registry.add("distance not too far", distance_not_too_far);
+        }
+    }
+
+    #[test]
+    fn test_shrink() {
+        let motion = |x| FuzzInput::MouseMotion(WrapVec2 { x, y: 2.5 });
+        let inputs = vec![
+            motion(1.0),
+            FuzzInput::RunFrame,
+            motion(120.5),
+            motion(3.0),
+            FuzzInput::RunFrame,
+            motion(4.0),
+            FuzzInput::RunFrame,
+            motion(250.5),
+            FuzzInput::RunFrameWithDelta(0.5),
+        ];
+
+        // fails with two frames, having a motion further than 100 in the last one
+        let still_fails = |inputs: &[FuzzInput]| {
+            let frames = inputs
+                .split_inclusive(FuzzInput::is_frame_end)
+                .collect::<Vec<_>>();
+
+            frames.len() >= 2
+                && frames[frames.len() - 1].iter().any(
+                    |input| matches!(input, FuzzInput::MouseMotion(motion) if motion.x > 100.0),
+                )
+        };
+        assert!(still_fails(&inputs));
+
+        let mut runs = 0;
+        let shrunk = shrink(inputs, |inputs| {
+            runs += 1;
+            still_fails(inputs)
+        });
+
+        assert_eq!(
+            shrunk,
+            vec![
+                FuzzInput::RunFrame,
+                FuzzInput::MouseMotion(WrapVec2 { x: 251.0, y: 0.0 })
+            ]
+        );
+        assert!(runs < 100, "{} runs", runs);
+    }
+
+    #[test]
+    fn test_minimize_invariant() {
+        let mut app = App::new();
+        MovingApp.enable_fuzzing_mode(&mut app);
+
+        let motion = |x| FuzzInput::MouseMotion(WrapVec2 { x, y: 2.5 });
+        let inputs = vec![
+            motion(10.0),
+            FuzzInput::RunFrame,
+            motion(20.0),
+            FuzzInput::RunFrame,
+            FuzzInput::RunFrame,
+            motion(150.5),
+            FuzzInput::RunFrame,
+            motion(5.0),
+            FuzzInput::RunFrame,
+        ];
+
+        // the violation moves to an earlier tick while shrinking, the message stays the same
+        let (shrunk, message) = minimize(&mut app, &inputs).unwrap();
+        assert_eq!(
+            message,
+            "FUZZ: invariant 'distance not too far' violated: moved too far"
+        );
+        assert_eq!(
+            shrunk,
+            vec![FuzzInput::MouseMotion(WrapVec2 { x: 151.0, y: 0.0 })]
+        );
+
+        assert_eq!(minimize(&mut app, &inputs[..5]), None);
+    }
+}
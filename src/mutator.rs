@@ -0,0 +1,509 @@
+use serde::de::DeserializeOwned;
+
+use crate::{
+    dict::variant_examples,
+    fuzz_input::FuzzInput,
+    input::{
+        gamepad::{WrapGamepad, WrapGamepadEventType},
+        keyboard::WrapKeyCode,
+        mouse::WrapMouseButton,
+        touch::WrapTouchPhase,
+        WrapElementState,
+    },
+    output::parse_commands_lenient,
+};
+
+/// Maximum number of mutations applied to an input at once
+const MAX_MUTATIONS: usize = 4;
+
+/// One in this many seeds is left to libFuzzer's byte-level mutation
+const BYTE_LEVEL_SEEDS: usize = 4;
+
+/// Coordinates that often hit edge cases: the origin, window edges and far outside of them
+pub(crate) const INTERESTING_COORDINATES: &[f32] =
+    &[0.0, 1.0, -1.0, 100.0, 640.0, 1280.0, -1e4, 1e9];
+
+/// Semantic mutator for libFuzzer, compatible with `libfuzzer_sys::fuzz_mutator!`
+///
+/// Decodes the input, applies a few event-level mutations (insert, delete, duplicate or reorder
+/// events, tweak coordinates, swap key codes, split or merge frames) and writes the result back
+/// as headerless frames, truncated to `max_size`. Frames that do not decode are dropped. Returns
+/// the new size, or `None` if no frame of the input decodes or for about a quarter of the seeds,
+/// in which case the caller should fall back to libFuzzer's own mutation:
+///
+/// ```ignore
+/// fuzz_mutator!(|data: &mut [u8], size: usize, max_size: usize, seed: u32| {
+///     bevy_fuzz::mutate(data, size, max_size, seed)
+///         .unwrap_or_else(|| fuzzer_mutate(data, size, max_size))
+/// });
+/// ```
+pub fn mutate(data: &mut [u8], size: usize, max_size: usize, seed: u32) -> Option<usize> {
+    let mut rng = Rng::new(seed);
+    if rng.below(BYTE_LEVEL_SEEDS) == 0 {
+        return None;
+    }
+
+    let (mut inputs, dropped) = parse_commands_lenient(data[..size].to_vec()).ok()?;
+    if inputs.is_empty() && dropped > 0 {
+        return None;
+    }
+    mutate_inputs(&mut inputs, &mut rng);
+
+    let max_size = max_size.min(data.len());
+    Some(write_frames(&inputs, &mut data[..max_size]))
+}
+
+/// Frame-level crossover for libFuzzer, compatible with `libfuzzer_sys::fuzz_crossover!`
+///
+/// Splits both parents into frames (at `RunFrame` and `RunFrameWithDelta`), and splices whole
+/// frames: a prefix of the first parent, a run of frames from the second parent and optionally the
+/// rest of the first parent. Frames of the parents that do not decode are skipped. Returns the
+/// size written into `out`.
+///
+/// ```ignore
+/// fuzz_crossover!(|data1: &[u8], data2: &[u8], out: &mut [u8], seed: u32| {
+///     bevy_fuzz::crossover(data1, data2, out, seed)
+/// });
+/// ```
+pub fn crossover(data1: &[u8], data2: &[u8], out: &mut [u8], seed: u32) -> usize {
+    let parse = |data: &[u8]| match parse_commands_lenient(data.to_vec()) {
+        Ok((inputs, _)) => inputs,
+        Err(_) => Vec::new(),
+    };
+    let parent1 = parse(data1);
+    let parent2 = parse(data2);
+
+    let mut rng = Rng::new(seed);
+    let child = splice_frames(&parent1, &parent2, &mut rng);
+
+    write_frames(&child, out)
+}
+
+fn splice_frames(parent1: &[FuzzInput], parent2: &[FuzzInput], rng: &mut Rng) -> Vec<FuzzInput> {
+    let frames1 = parent1
+        .split_inclusive(FuzzInput::is_frame_end)
+        .collect::<Vec<_>>();
+    let frames2 = parent2
+        .split_inclusive(FuzzInput::is_frame_end)
+        .collect::<Vec<_>>();
+
+    let split = rng.below(frames1.len() + 1);
+    let start = rng.below(frames2.len() + 1);
+    let end = start + rng.below(frames2.len() - start + 1);
+
+    let mut child = frames1[..split].concat();
+    child.extend(frames2[start..end].concat());
+    if rng.below(2) == 0 {
+        child.extend(frames1[split..].concat());
+    }
+
+    child
+}
+
+/// Writes as many whole frames of the inputs as fit into `out`, returning the written size
+pub(crate) fn write_frames(inputs: &[FuzzInput], out: &mut [u8]) -> usize {
+    let mut size = 0;
+
+    for input in inputs {
+        let frame = postcard::to_stdvec_cobs(input).unwrap();
+        if size + frame.len() > out.len() {
+            break;
+        }
+
+        out[size..size + frame.len()].copy_from_slice(&frame);
+        size += frame.len();
+    }
+
+    size
+}
+
+pub(crate) fn mutate_inputs(inputs: &mut Vec<FuzzInput>, rng: &mut Rng) {
+    let mutations = 1 + rng.below(MAX_MUTATIONS);
+    let mut applied = 0;
+
+    // not every mutation applies to every input (e.g. no key events to swap), those are retried
+    for _ in 0..mutations * 8 {
+        if applied == mutations {
+            break;
+        }
+
+        if mutate_once(inputs, rng) {
+            applied += 1;
+        }
+    }
+}
+
+/// Applies a single random mutation, returns false if it did not apply to the inputs
+fn mutate_once(inputs: &mut Vec<FuzzInput>, rng: &mut Rng) -> bool {
+    let len = inputs.len();
+
+    match rng.below(8) {
+        // insert a new event
+        0 => {
+            let input = random_input(rng);
+            inputs.insert(rng.below(len + 1), input);
+        }
+        // delete an event
+        1 if len > 0 => {
+            inputs.remove(rng.below(len));
+        }
+        // duplicate an event
+        2 if len > 0 => {
+            let input = inputs[rng.below(len)].clone();
+            inputs.insert(rng.below(len + 1), input);
+        }
+        // swap two events
+        3 if len > 1 => inputs.swap(rng.below(len), rng.below(len)),
+        // tweak the coordinates of an event
+        4 => match pick(inputs, rng, has_coordinates) {
+            Some(input) => tweak_coordinates(input, rng),
+            None => return false,
+        },
+        // swap a key code
+        5 => match pick(inputs, rng, has_key_code) {
+            Some(input) => swap_key_code(input, rng),
+            None => return false,
+        },
+        // split a frame
+        6 => inputs.insert(rng.below(len + 1), FuzzInput::RunFrame),
+        // merge two frames
+        7 => match pick_index(inputs, rng, FuzzInput::is_frame_end) {
+            Some(index) => {
+                inputs.remove(index);
+            }
+            None => return false,
+        },
+        _ => return false,
+    }
+
+    true
+}
+
+fn pick_index(
+    inputs: &[FuzzInput],
+    rng: &mut Rng,
+    filter: impl Fn(&FuzzInput) -> bool,
+) -> Option<usize> {
+    let candidates = inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| filter(input))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+
+    match candidates.len() {
+        0 => None,
+        len => Some(candidates[rng.below(len)]),
+    }
+}
+
+fn pick<'a>(
+    inputs: &'a mut [FuzzInput],
+    rng: &mut Rng,
+    filter: impl Fn(&FuzzInput) -> bool,
+) -> Option<&'a mut FuzzInput> {
+    let index = pick_index(inputs, rng, filter)?;
+    Some(&mut inputs[index])
+}
+
+/// An event of a random variant, with random values
+fn random_input(rng: &mut Rng) -> FuzzInput {
+    let examples = variant_examples();
+    let mut input = examples[rng.below(examples.len())].clone();
+
+    if let Some((x, y)) = input.coordinates_mut() {
+        *x = random_coordinate(rng);
+        *y = random_coordinate(rng);
+    }
+
+    match &mut input {
+        FuzzInput::MouseButton(mouse_button) => {
+            mouse_button.button = match rng.below(4) {
+                0 => WrapMouseButton::Left,
+                1 => WrapMouseButton::Right,
+                2 => WrapMouseButton::Middle,
+                _ => WrapMouseButton::Other(rng.below(16) as u16),
+            };
+            mouse_button.state = random_state(rng);
+        }
+        FuzzInput::KeyboardInput(keyboard) => {
+            keyboard.scan_code = rng.below(256) as u32;
+            keyboard.key_code = random_optional_key_code(rng);
+            keyboard.state = random_state(rng);
+        }
+        FuzzInput::GamepadEvent(gamepad) => {
+            gamepad.gamepad = WrapGamepad(rng.below(4));
+            let value = [-1.0, -0.5, 0.0, 0.5, 1.0][rng.below(5)];
+            gamepad.event_type = match rng.below(4) {
+                0 => WrapGamepadEventType::Connected,
+                1 => WrapGamepadEventType::Disconnected,
+                2 => WrapGamepadEventType::ButtonChanged(random_variant(rng, 32), value),
+                _ => WrapGamepadEventType::AxisChanged(random_variant(rng, 8), value),
+            };
+        }
+        FuzzInput::Touch(touch) => {
+            touch.phase = match rng.below(4) {
+                0 => WrapTouchPhase::Started,
+                1 => WrapTouchPhase::Moved,
+                2 => WrapTouchPhase::Ended,
+                _ => WrapTouchPhase::Cancelled,
+            };
+            touch.id = rng.below(4) as u64;
+        }
+        FuzzInput::ReceivedCharacter(character) => {
+            character.char = char::from_u32(rng.below(0x800) as u32).unwrap_or('a');
+        }
+        FuzzInput::WindowFocused(focused) => focused.focused = rng.below(2) == 0,
+        FuzzInput::WindowMoved(moved) => {
+            moved.position.x = random_coordinate(rng) as i32;
+            moved.position.y = random_coordinate(rng) as i32;
+        }
+        FuzzInput::WindowScaleFactorChanged(changed) => {
+            changed.scale_factor = random_scale_factor(rng)
+        }
+        FuzzInput::WindowBackendScaleFactorChanged(changed) => {
+            changed.scale_factor = random_scale_factor(rng)
+        }
+        FuzzInput::RunFrameWithDelta(delta) => *delta = rng.below(1000) as f32 / 1000.0,
+        _ => {}
+    }
+
+    input
+}
+
+fn random_state(rng: &mut Rng) -> WrapElementState {
+    match rng.below(2) {
+        0 => WrapElementState::Pressed,
+        _ => WrapElementState::Released,
+    }
+}
+
+fn random_key_code(rng: &mut Rng) -> WrapKeyCode {
+    // key codes are a dense enum, the index is retried until it is a valid variant
+    loop {
+        if let Some(key_code) = WrapKeyCode::from_index(rng.below(256) as u32) {
+            return key_code;
+        }
+    }
+}
+
+/// A random unit variant of an enum, by decoding variant indices below `max` until one is valid
+fn random_variant<T: DeserializeOwned>(rng: &mut Rng, max: usize) -> T {
+    loop {
+        let index = postcard::to_stdvec(&(rng.below(max) as u32)).unwrap();
+        if let Ok(variant) = postcard::from_bytes(&index) {
+            return variant;
+        }
+    }
+}
+
+fn random_optional_key_code(rng: &mut Rng) -> Option<WrapKeyCode> {
+    // keys without a key code are only identified by their scan code
+    match rng.below(8) {
+        0 => None,
+        _ => Some(random_key_code(rng)),
+    }
+}
+
+fn random_scale_factor(rng: &mut Rng) -> f64 {
+    [0.0, 0.5, 1.0, 1.5, 2.0, 3.0][rng.below(6)]
+}
+
+fn random_coordinate(rng: &mut Rng) -> f32 {
+    match rng.below(4) {
+        0 => INTERESTING_COORDINATES[rng.below(INTERESTING_COORDINATES.len())],
+        _ => rng.below(1280) as f32,
+    }
+}
+
+fn has_coordinates(input: &FuzzInput) -> bool {
+    matches!(
+        input,
+        FuzzInput::MouseWheel(_)
+            | FuzzInput::MouseMotion(_)
+            | FuzzInput::CursorMoved(_)
+            | FuzzInput::WindowResized(_)
+            | FuzzInput::Touch(_)
+    )
+}
+
+fn has_key_code(input: &FuzzInput) -> bool {
+    matches!(input, FuzzInput::KeyboardInput(_))
+}
+
+fn tweak_coordinates(input: &mut FuzzInput, rng: &mut Rng) {
+    let (x, y) = match input.coordinates_mut() {
+        Some(coordinates) => coordinates,
+        None => return,
+    };
+
+    let coordinate = if rng.below(2) == 0 { x } else { y };
+    *coordinate = match rng.below(4) {
+        0 => *coordinate + (rng.below(33) as f32 - 16.0),
+        1 => *coordinate * 2.0,
+        2 => *coordinate / 2.0,
+        _ => INTERESTING_COORDINATES[rng.below(INTERESTING_COORDINATES.len())],
+    };
+}
+
+fn swap_key_code(input: &mut FuzzInput, rng: &mut Rng) {
+    if let FuzzInput::KeyboardInput(keyboard) = input {
+        keyboard.key_code = Some(random_key_code(rng));
+    }
+}
+
+/// Small deterministic PRNG (splitmix64), seeded by libFuzzer
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u32) -> Self {
+        Rng(u64::from(seed))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`, `n` must not be zero
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::WrapVec2,
+        output::{encode_commands, encode_frames, parse_commands},
+    };
+
+    #[test]
+    fn test_mutate() {
+        let inputs = vec![
+            FuzzInput::MouseMotion(WrapVec2 { x: 1.0, y: 2.0 }),
+            FuzzInput::RunFrame,
+            FuzzInput::MouseMotion(WrapVec2 { x: 3.0, y: 4.0 }),
+            FuzzInput::RunFrame,
+        ];
+        let encoded = encode_commands(&inputs);
+
+        let (mut changed, mut byte_level) = (0, 0);
+        for seed in 0..100 {
+            let mut data = encoded.clone();
+            data.resize(64, 0);
+
+            let size = match mutate(&mut data, encoded.len(), 64, seed) {
+                Some(size) => size,
+                None => {
+                    byte_level += 1;
+                    continue;
+                }
+            };
+            assert!(size <= 64);
+
+            if parse_commands(data[..size].to_vec()).unwrap() != inputs {
+                changed += 1;
+            }
+
+            // deterministic for a seed
+            let mut again = encoded.clone();
+            again.resize(64, 0);
+            assert_eq!(mutate(&mut again, encoded.len(), 64, seed), Some(size));
+            assert_eq!(again[..size], data[..size]);
+        }
+        assert!((10..40).contains(&byte_level), "{}", byte_level);
+        assert!(changed > 90 - byte_level);
+
+        for seed in 0..10 {
+            let mut garbage = vec![1, 2, 3, 0];
+            assert_eq!(mutate(&mut garbage, 4, 4, seed), None);
+        }
+    }
+
+    #[test]
+    fn test_mutate_corrupt_frame() {
+        let motion = |x| FuzzInput::MouseMotion(WrapVec2 { x, y: 2.0 });
+
+        let mut encoded = encode_commands(&[motion(1.0), FuzzInput::RunFrame]);
+        encoded.extend([2, 200, 0]); // unknown variant
+        encoded.extend(encode_frames(&[motion(3.0), FuzzInput::RunFrame]));
+
+        let mutated = (0..20)
+            .filter_map(|seed| {
+                let mut data = encoded.clone();
+                data.resize(64, 0);
+
+                let size = mutate(&mut data, encoded.len(), 64, seed)?;
+                Some(parse_commands(data[..size].to_vec()).unwrap())
+            })
+            .collect::<Vec<_>>();
+
+        // the corrupt frame is dropped, the frames around it are kept and mutated
+        assert!(mutated.len() > 10);
+        assert!(mutated.iter().any(|inputs| inputs.contains(&motion(1.0))));
+        assert!(mutated.iter().any(|inputs| inputs.contains(&motion(3.0))));
+    }
+
+    #[test]
+    fn test_random_input() {
+        let mut rng = Rng::new(0);
+        let inputs = (0..1000)
+            .map(|_| random_input(&mut rng))
+            .collect::<Vec<_>>();
+
+        // every variant is generated
+        for example in variant_examples() {
+            assert!(inputs
+                .iter()
+                .any(|input| std::mem::discriminant(input) == std::mem::discriminant(&example)));
+        }
+
+        let scan_codes = inputs
+            .iter()
+            .filter_map(|input| match input {
+                FuzzInput::KeyboardInput(keyboard) => Some(keyboard.scan_code),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(scan_codes
+            .iter()
+            .any(|scan_code| *scan_code != scan_codes[0]));
+    }
+
+    #[test]
+    fn test_crossover() {
+        let motion = |x| FuzzInput::MouseMotion(WrapVec2 { x, y: 0.0 });
+        let parent1 = vec![
+            motion(1.0),
+            FuzzInput::RunFrame,
+            motion(2.0),
+            FuzzInput::RunFrame,
+        ];
+        let parent2 = vec![
+            motion(3.0),
+            FuzzInput::RunFrameWithDelta(0.5),
+            motion(4.0),
+            FuzzInput::RunFrame,
+        ];
+        let frames = [&parent1[..2], &parent1[2..], &parent2[..2], &parent2[2..]];
+
+        let data1 = encode_commands(&parent1);
+        let data2 = encode_commands(&parent2);
+
+        for seed in 0..100 {
+            let mut out = vec![0u8; 256];
+            let size = crossover(&data1, &data2, &mut out, seed);
+
+            // only whole frames of the parents
+            let child = parse_commands(out[..size].to_vec()).unwrap();
+            for frame in child.split_inclusive(FuzzInput::is_frame_end) {
+                assert!(frames.contains(&frame), "seed {}: {:?}", seed, child);
+            }
+        }
+    }
+}
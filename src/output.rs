@@ -1,26 +1,46 @@
-use std::{fs::File, io::Write};
+use std::{fs::File, io::Write, path::Path};
 
 use bevy::{
+    ecs::system::SystemParam,
     input::{
+        gamepad::GamepadEventRaw,
         keyboard::KeyboardInput,
         mouse::{MouseButtonInput, MouseMotion, MouseWheel},
+        touch::TouchInput,
     },
     prelude::*,
-    window::WindowResized,
+    window::{
+        CursorEntered, CursorLeft, FileDragAndDrop, ReceivedCharacter,
+        WindowBackendScaleFactorChanged, WindowCloseRequested, WindowFocused, WindowMoved,
+        WindowResized, WindowScaleFactorChanged,
+    },
 };
 
-use crate::{math::WrapVec2, prelude::FuzzInput};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    format::{Header, ParseError},
+    math::WrapVec2,
+    migrate,
+    prelude::FuzzInput,
+    window::{
+        event::{
+            WrapCursorEntered, WrapCursorLeft, WrapCursorMoved, WrapFileDragAndDrop,
+            WrapReceivedCharacter, WrapWindowBackendScaleFactorChanged, WrapWindowCloseRequested,
+            WrapWindowFocused, WrapWindowMoved, WrapWindowResized, WrapWindowScaleFactorChanged,
+        },
+        WindowIdMap,
+    },
+};
 
 #[derive(Default)]
 pub struct EventOutputPlugin;
 
 impl Plugin for EventOutputPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(EventOutput {
-            buffer: vec![0u8; 1024 * 1024],
-            file: File::create("input-recording.bin").unwrap(),
-        })
-        .add_system(output_input_events_system);
+        app.insert_resource(EventOutput::create("input-recording.bin").unwrap())
+            .insert_resource(WindowIdMap::default())
+            .add_system(output_input_events_system);
     }
 }
 
@@ -30,94 +50,265 @@ pub struct EventOutput {
 }
 
 impl EventOutput {
-    pub fn write_input_actions(&mut self, input_actions: &[FuzzInput]) -> Result<(), ()> {
+    /// Creates the recording file, and writes the format header into it
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut file = File::create(path)?;
+        Header::current().write(&mut file)?;
+
+        Ok(Self {
+            buffer: vec![0u8; 1024 * 1024],
+            file,
+        })
+    }
+
+    pub fn write_input_actions(&mut self, input_actions: &[FuzzInput]) -> std::io::Result<()> {
         for action in input_actions {
             let cobs_encoded_data =
                 postcard::to_slice_cobs(action, self.buffer.as_mut_slice()).unwrap();
 
-            self.file.write(cobs_encoded_data).unwrap(); // TODO use bufwriter for performance?
+            self.file.write_all(cobs_encoded_data)?; // TODO use bufwriter for performance?
         }
 
         Ok(())
     }
 }
 
+/// Event readers for all of the input events that are recorded
+#[derive(SystemParam)]
+pub struct InputEventReaders<'w, 's> {
+    window_resized: EventReader<'w, 's, WindowResized>,
+    mouse_button_input: EventReader<'w, 's, MouseButtonInput>,
+    mouse_wheel: EventReader<'w, 's, MouseWheel>,
+    keyboard_input: EventReader<'w, 's, KeyboardInput>,
+    mouse_motion: EventReader<'w, 's, MouseMotion>,
+    cursor_moved: EventReader<'w, 's, CursorMoved>,
+    gamepad: EventReader<'w, 's, GamepadEventRaw>,
+    touch_input: EventReader<'w, 's, TouchInput>,
+    received_character: EventReader<'w, 's, ReceivedCharacter>,
+}
+
+/// Event readers for the window lifecycle events that are recorded
+#[derive(SystemParam)]
+pub struct WindowEventReaders<'w, 's> {
+    window_focused: EventReader<'w, 's, WindowFocused>,
+    window_moved: EventReader<'w, 's, WindowMoved>,
+    scale_factor_changed: EventReader<'w, 's, WindowScaleFactorChanged>,
+    backend_scale_factor_changed: EventReader<'w, 's, WindowBackendScaleFactorChanged>,
+    cursor_entered: EventReader<'w, 's, CursorEntered>,
+    cursor_left: EventReader<'w, 's, CursorLeft>,
+    close_requested: EventReader<'w, 's, WindowCloseRequested>,
+    file_drag_and_drop: EventReader<'w, 's, FileDragAndDrop>,
+}
+
 pub fn output_input_events_system(
-    mut window_resized_events: EventReader<WindowResized>,
-    mut mouse_button_input_events: EventReader<MouseButtonInput>,
-    mut mouse_wheel_events: EventReader<MouseWheel>,
-    mut keyboard_input_events: EventReader<KeyboardInput>,
-    mut mouse_motion_events: EventReader<MouseMotion>,
-    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut events: InputEventReaders,
+    mut window_events: WindowEventReaders,
     mut event_output: ResMut<EventOutput>,
+    mut window_ids: ResMut<WindowIdMap>,
+    time: Res<Time>,
 ) {
     let mut f_inp = Vec::new();
 
     f_inp.extend(
-        window_resized_events
+        events
+            .window_resized
             .iter()
-            .map(|v| FuzzInput::WindowResized(v.into())),
+            .map(|v| FuzzInput::WindowResized(WrapWindowResized::from_event(v, &mut window_ids))),
     );
     f_inp.extend(
-        mouse_button_input_events
+        events
+            .mouse_button_input
             .iter()
             .map(|v| FuzzInput::MouseButton(v.into())),
     );
     f_inp.extend(
-        mouse_wheel_events
+        events
+            .mouse_wheel
             .iter()
             .map(|v| FuzzInput::MouseWheel(v.into())),
     );
     f_inp.extend(
-        keyboard_input_events
+        events
+            .keyboard_input
             .iter()
             .map(|v| FuzzInput::KeyboardInput(v.into())),
     );
-    f_inp.extend(mouse_motion_events.iter().map(|v| {
+    f_inp.extend(events.mouse_motion.iter().map(|v| {
         FuzzInput::MouseMotion(WrapVec2 {
             x: v.delta.x,
             y: v.delta.y,
         })
     }));
     f_inp.extend(
-        cursor_moved_events
+        events
+            .cursor_moved
+            .iter()
+            .map(|v| FuzzInput::CursorMoved(WrapCursorMoved::from_event(v, &mut window_ids))),
+    );
+    f_inp.extend(
+        events
+            .gamepad
+            .iter()
+            .map(|v| FuzzInput::GamepadEvent(v.into())),
+    );
+    f_inp.extend(
+        events
+            .touch_input
+            .iter()
+            .map(|v| FuzzInput::Touch(v.into())),
+    );
+    f_inp.extend(events.received_character.iter().map(|v| {
+        FuzzInput::ReceivedCharacter(WrapReceivedCharacter::from_event(v, &mut window_ids))
+    }));
+    f_inp.extend(
+        window_events
+            .window_focused
+            .iter()
+            .map(|v| FuzzInput::WindowFocused(WrapWindowFocused::from_event(v, &mut window_ids))),
+    );
+    f_inp.extend(
+        window_events
+            .window_moved
+            .iter()
+            .map(|v| FuzzInput::WindowMoved(WrapWindowMoved::from_event(v, &mut window_ids))),
+    );
+    f_inp.extend(window_events.scale_factor_changed.iter().map(|v| {
+        FuzzInput::WindowScaleFactorChanged(WrapWindowScaleFactorChanged::from_event(
+            v,
+            &mut window_ids,
+        ))
+    }));
+    f_inp.extend(window_events.backend_scale_factor_changed.iter().map(|v| {
+        FuzzInput::WindowBackendScaleFactorChanged(WrapWindowBackendScaleFactorChanged::from_event(
+            v,
+            &mut window_ids,
+        ))
+    }));
+    f_inp.extend(
+        window_events
+            .cursor_entered
+            .iter()
+            .map(|v| FuzzInput::CursorEntered(WrapCursorEntered::from_event(v, &mut window_ids))),
+    );
+    f_inp.extend(
+        window_events
+            .cursor_left
             .iter()
-            .map(|v| FuzzInput::CursorMoved(v.into())),
+            .map(|v| FuzzInput::CursorLeft(WrapCursorLeft::from_event(v, &mut window_ids))),
+    );
+    f_inp.extend(window_events.close_requested.iter().map(|v| {
+        FuzzInput::WindowCloseRequested(WrapWindowCloseRequested::from_event(v, &mut window_ids))
+    }));
+    f_inp.extend(
+        window_events.file_drag_and_drop.iter().map(|v| {
+            FuzzInput::FileDragAndDrop(WrapFileDragAndDrop::from_event(v, &mut window_ids))
+        }),
     );
 
-    f_inp.extend([FuzzInput::RunFrame]);
+    f_inp.extend([FuzzInput::RunFrameWithDelta(time.delta_seconds())]);
 
     event_output.write_input_actions(&f_inp).unwrap();
 }
 
-pub fn parse_commands(mut input: Vec<u8>) -> Result<Vec<FuzzInput>, ()> {
-    let mut buf = vec![0u8; 1024];
+/// Parses a recording into input events
+///
+/// Inputs starting with the format header are validated against it (older format versions are
+/// migrated), inputs without a header (e.g. produced by libFuzzer) are decoded as raw frames.
+pub fn parse_commands(input: Vec<u8>) -> Result<Vec<FuzzInput>, ParseError> {
+    match Header::read(&input)? {
+        Some((header, header_len)) => {
+            header.validate()?;
+            migrate::decode_version(header.format_version, input[header_len..].to_vec(), false)
+                .map(|(inputs, _)| inputs)
+        }
+        None => parse_frames(input),
+    }
+}
+
+/// Parses a recording like `parse_commands`, but skips frames that cannot be decoded
+///
+/// Returns the input events, and the number of skipped frames. An invalid format header is still
+/// an error.
+pub fn parse_commands_lenient(input: Vec<u8>) -> Result<(Vec<FuzzInput>, usize), ParseError> {
+    match Header::read(&input)? {
+        Some((header, header_len)) => {
+            header.validate()?;
+            migrate::decode_version(header.format_version, input[header_len..].to_vec(), true)
+        }
+        None => decode_frames(input, true),
+    }
+}
+
+/// Parses headerless COBS frames into input events
+pub fn parse_frames(input: Vec<u8>) -> Result<Vec<FuzzInput>, ParseError> {
+    decode_frames(input, false).map(|(inputs, _)| inputs)
+}
+
+/// Encodes input events into a recording, including the format header
+pub fn encode_commands(input: &[FuzzInput]) -> Vec<u8> {
+    let mut data = Vec::new();
+    Header::current().write(&mut data).unwrap();
+    data.extend(encode_frames(input));
+
+    data
+}
+
+/// Encodes input events into headerless COBS frames
+pub(crate) fn encode_frames(input: &[FuzzInput]) -> Vec<u8> {
+    let mut data = Vec::new();
+
+    for action in input {
+        data.extend(postcard::to_stdvec_cobs(action).unwrap());
+    }
+
+    data
+}
+
+/// Decodes headerless COBS frames
+///
+/// Returns the decoded frames, and the number of frames that could not be decoded. Those fail
+/// the whole input, unless `lenient` is set.
+pub(crate) fn decode_frames<T: DeserializeOwned>(
+    mut input: Vec<u8>,
+    lenient: bool,
+) -> Result<(Vec<T>, usize), ParseError> {
+    let mut buf = vec![0u8; input.len()];
 
     let mut offset = 0;
     let mut fuzz_inputs = Vec::new();
-    loop {
+    let mut dropped = 0;
+    while offset < input.len() {
         let input_slice = &mut input[offset..];
         let mut cd = postcard_cobs::CobsDecoder::new(&mut buf);
 
-        match cd.push(&input_slice) {
-            Ok(v) => match v {
-                Some((_, used_data)) => {
-                    match postcard::from_bytes_cobs::<FuzzInput>(&mut input_slice[..used_data]) {
-                        Ok(v) => fuzz_inputs.push(v),
-                        Err(_) => {
-                            return Err(());
-                        }
-                    }
-
-                    offset += used_data;
+        match cd.push(input_slice) {
+            Ok(Some((_, used_data))) => {
+                match postcard::from_bytes_cobs::<T>(&mut input_slice[..used_data]) {
+                    Ok(v) => fuzz_inputs.push(v),
+                    Err(_) if lenient => dropped += 1,
+                    Err(_) => return Err(ParseError::InvalidFrame { offset }),
                 }
-                None => break,
-            },
+
+                offset += used_data;
+            }
+            // a broken COBS frame, lenient decoding continues after its terminator
+            Err(_) if lenient => {
+                dropped += 1;
+                match input_slice.iter().position(|v| *v == 0) {
+                    Some(end) => offset += end + 1,
+                    None => break,
+                }
+            }
+            // trailing bytes without a terminator
+            Ok(None) => {
+                dropped += 1;
+                break;
+            }
             Err(_) => break,
         }
     }
 
-    Ok(fuzz_inputs)
+    Ok((fuzz_inputs, dropped))
 }
 
 #[cfg(test)]
@@ -140,4 +331,99 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_parse_commands_touch() {
+        use crate::input::touch::{WrapTouchInput, WrapTouchPhase};
+
+        let touch = |id, phase, x, y| {
+            FuzzInput::Touch(WrapTouchInput {
+                phase,
+                position: WrapVec2 { x, y },
+                force: None,
+                id,
+            })
+        };
+
+        let inputs = vec![
+            touch(0, WrapTouchPhase::Started, 10.0, 10.0),
+            touch(1, WrapTouchPhase::Started, 50.0, 50.0),
+            FuzzInput::RunFrame,
+            touch(0, WrapTouchPhase::Moved, 5.0, 5.0),
+            touch(1, WrapTouchPhase::Cancelled, 60.0, 60.0),
+            FuzzInput::RunFrame,
+        ];
+
+        let mut buffer = vec![0u8; 1024];
+        let mut data = Vec::new();
+        for input in &inputs {
+            data.extend_from_slice(postcard::to_slice_cobs(input, &mut buffer).unwrap());
+        }
+
+        assert_eq!(parse_commands(data).unwrap(), inputs);
+    }
+
+    #[test]
+    fn test_parse_commands_legacy_keyboard_input() {
+        // KeyboardInput { key_code: Some(A), state: Pressed }, recorded without scan code, is
+        // only converted by `migrate`
+        let data = vec![4, 1, 1, 10, 1, 0];
+
+        let commands = parse_commands(data).unwrap();
+
+        assert_eq!(&commands, &[FuzzInput::Reserved]);
+    }
+
+    #[test]
+    fn test_parse_commands_with_header() {
+        let mut data = Vec::new();
+        Header::current().write(&mut data).unwrap();
+        data.extend([2, 3, 1, 3, 128, 192, 1, 3, 128, 63, 0]);
+
+        assert_eq!(
+            parse_commands(data.clone()).unwrap(),
+            &[FuzzInput::MouseMotion(WrapVec2 { x: -4.0, y: 1.0 })]
+        );
+
+        data[crate::format::MAGIC.len()] = 0xff;
+        assert!(matches!(
+            parse_commands(data),
+            Err(ParseError::UnsupportedFormatVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_commands_lenient() {
+        let motion = |x| FuzzInput::MouseMotion(WrapVec2 { x, y: 1.0 });
+
+        let mut data = encode_commands(&[motion(-4.0)]);
+        data.extend([2, 200, 0]); // unknown variant
+        data.extend([5, 1, 0]); // broken COBS frame
+        data.extend(encode_frames(&[motion(26.0)]));
+        data.extend([3, 1]); // truncated frame
+
+        assert_eq!(
+            parse_commands(data.clone()),
+            Err(ParseError::InvalidFrame { offset: 11 })
+        );
+        assert_eq!(
+            parse_commands_lenient(data).unwrap(),
+            (vec![motion(-4.0), motion(26.0)], 3)
+        );
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_round_trip() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let bytes = (1..=255u8).cycle().take(4096).collect::<Vec<_>>();
+        let inputs = Vec::<FuzzInput>::arbitrary_take_rest(Unstructured::new(&bytes)).unwrap();
+        assert!(!inputs.is_empty());
+
+        // compared encoded, arbitrary floats may be NaN
+        let encoded = encode_commands(&inputs);
+        let decoded = parse_commands(encoded.clone()).unwrap();
+        assert_eq!(encode_commands(&decoded), encoded);
+    }
 }
@@ -0,0 +1,270 @@
+use std::{
+    fmt::Display,
+    panic,
+    sync::{Arc, Mutex, MutexGuard, Once, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{ecs::schedule::GraphNode, prelude::*};
+
+use crate::fuzz_input::FuzzInput;
+
+/// Number of inputs leading to a panic that are printed in the panic report
+const TRAILING_INPUTS: usize = 16;
+
+/// Every installed `RunnerProgress`, reported by the panic hook
+static INSTALLED: Mutex<Vec<Installed>> = Mutex::new(Vec::new());
+
+/// The panic hook is chained once, however many apps are set up
+static PANIC_HOOK: Once = Once::new();
+
+/// Position of the fuzz runner, updated by the runner and the stage marker systems
+#[derive(Default)]
+struct Progress {
+    /// Inputs of the running iteration, `None` between iterations
+    inputs: Option<Vec<FuzzInput>>,
+    /// Number of inputs fed into the world so far
+    consumed: usize,
+    /// Number and start time of the tick being run
+    tick: Option<(usize, Instant)>,
+    /// Part of the tick being run
+    stage: TickStage,
+}
+
+/// Part of a tick: feeding input events, running a stage (by index into `stages`) or checking the
+/// invariants
+#[derive(Clone, Copy, Default)]
+enum TickStage {
+    #[default]
+    Feeding,
+    Stage(usize),
+    Invariants,
+}
+
+/// Progress of an installed `RunnerProgress`, and the stages of its app
+struct Installed {
+    progress: Weak<Mutex<Progress>>,
+    stages: Arc<Vec<StageInfo>>,
+}
+
+/// A stage of the schedule, and the systems in it
+struct StageInfo {
+    name: String,
+    systems: Vec<String>,
+}
+
+impl StageInfo {
+    fn describe(stages: &[StageInfo], stage: TickStage) -> String {
+        match stage {
+            TickStage::Feeding => String::from("none (feeding input events)"),
+            TickStage::Stage(index) => match stages.get(index) {
+                Some(stage) => format!("{} (systems: {})", stage.name, stage.systems.join(", ")),
+                None => String::from("unknown"),
+            },
+            TickStage::Invariants => String::from("none (checking invariants)"),
+        }
+    }
+}
+
+/// Tracks the tick and stage being run, for crash reports
+///
+/// A panic during a fuzz iteration prints the position and the inputs leading to it. With a
+/// `max_tick_duration`, a watchdog thread aborts the process when a tick runs longer, reporting
+/// the stage that was running. Bevy runs the systems of a stage in parallel, so the reports list
+/// all systems of the stage.
+pub(crate) struct RunnerProgress {
+    progress: Arc<Mutex<Progress>>,
+}
+
+impl RunnerProgress {
+    /// Adds a marker system at the start of every stage, registers the progress with the panic
+    /// hook (chained on the first call) and starts the watchdog thread, then inserts the
+    /// `RunnerProgress` resource. Does nothing if the app already tracks its progress.
+    pub(crate) fn install(app: &mut App, max_tick_duration: Option<Duration>) {
+        if app.world.contains_resource::<RunnerProgress>() {
+            return;
+        }
+
+        let progress = Arc::new(Mutex::new(Progress::default()));
+
+        let labels = app
+            .schedule
+            .iter_stages()
+            .map(|(label, _)| label.dyn_clone())
+            .collect::<Vec<_>>();
+
+        let mut stages = Vec::new();
+        for label in labels {
+            let stage = match app.schedule.get_stage_mut::<SystemStage>(&*label) {
+                Some(stage) => stage,
+                None => continue,
+            };
+
+            let systems = stage
+                .exclusive_at_start_systems()
+                .iter()
+                .map(|system| system.name())
+                .chain(stage.parallel_systems().iter().map(|system| system.name()))
+                .chain(
+                    stage
+                        .exclusive_before_commands_systems()
+                        .iter()
+                        .map(|system| system.name()),
+                )
+                .chain(
+                    stage
+                        .exclusive_at_end_systems()
+                        .iter()
+                        .map(|system| system.name()),
+                )
+                .map(|name| name.into_owned())
+                .collect();
+
+            let index = stages.len();
+            let marker_progress = progress.clone();
+            stage.add_system(
+                (move |_: &mut World| {
+                    lock(&marker_progress).stage = TickStage::Stage(index);
+                })
+                .exclusive_system()
+                .at_start(),
+            );
+
+            stages.push(StageInfo {
+                name: format!("{:?}", label),
+                systems,
+            });
+        }
+
+        let stages = Arc::new(stages);
+
+        PANIC_HOOK.call_once(|| {
+            let previous_hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                for installed in lock(&INSTALLED).iter() {
+                    report_panic(&installed.progress, &installed.stages, info);
+                }
+                previous_hook(info);
+            }));
+        });
+
+        let mut installed = lock(&INSTALLED);
+        installed.retain(|installed| installed.progress.strong_count() > 0);
+        installed.push(Installed {
+            progress: Arc::downgrade(&progress),
+            stages: stages.clone(),
+        });
+        drop(installed);
+
+        if let Some(max_tick_duration) = max_tick_duration {
+            let weak_progress = Arc::downgrade(&progress);
+            thread::spawn(move || watch(weak_progress, stages, max_tick_duration));
+        }
+
+        app.insert_resource(RunnerProgress { progress });
+    }
+
+    pub(crate) fn iteration_started(&self, inputs: &[FuzzInput]) {
+        let mut progress = lock(&self.progress);
+        progress.inputs = Some(inputs.to_vec());
+        progress.consumed = 0;
+    }
+
+    pub(crate) fn iteration_finished(&self) {
+        let mut progress = lock(&self.progress);
+        progress.inputs = None;
+        progress.tick = None;
+    }
+
+    pub(crate) fn tick_started(&self, tick_no: usize) {
+        let mut progress = lock(&self.progress);
+        progress.tick = Some((tick_no, Instant::now()));
+        progress.stage = TickStage::Feeding;
+    }
+
+    pub(crate) fn invariants_started(&self) {
+        lock(&self.progress).stage = TickStage::Invariants;
+    }
+
+    pub(crate) fn inputs_consumed(&self, consumed: usize) {
+        lock(&self.progress).consumed = consumed;
+    }
+
+    pub(crate) fn tick_finished(&self) {
+        lock(&self.progress).tick = None;
+    }
+}
+
+/// Locks the mutex, also after a panic while it was locked
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Prints where the fuzz iteration was, and the inputs that led there
+fn report_panic(progress: &Weak<Mutex<Progress>>, stages: &[StageInfo], info: &dyn Display) {
+    let progress = match progress.upgrade() {
+        Some(progress) => progress,
+        None => return,
+    };
+    let progress = lock(&progress);
+    let inputs = match &progress.inputs {
+        Some(inputs) => inputs,
+        None => return,
+    };
+
+    eprintln!("FUZZ: panic during fuzz run: {}", info);
+    match progress.tick {
+        Some((tick_no, _)) => {
+            eprintln!("\ttick: {}", tick_no);
+            eprintln!("\tstage: {}", StageInfo::describe(stages, progress.stage));
+        }
+        None => eprintln!("\ttick: none (resetting the app, running startup systems)"),
+    }
+
+    let consumed = progress.consumed.min(inputs.len());
+    match consumed.checked_sub(1) {
+        Some(last) => eprintln!("\tlast consumed input: {} of {}", last, inputs.len()),
+        None => eprintln!("\tlast consumed input: none of {}", inputs.len()),
+    }
+
+    let first = consumed.saturating_sub(TRAILING_INPUTS);
+    eprintln!("\ttrailing inputs:");
+    for (idx, input) in inputs.iter().enumerate().take(consumed).skip(first) {
+        eprintln!("\t\t{}: {:?}", idx, input);
+    }
+}
+
+/// Watchdog thread, runs until the app (and its `RunnerProgress`) is dropped
+fn watch(
+    progress: Weak<Mutex<Progress>>,
+    stages: Arc<Vec<StageInfo>>,
+    max_tick_duration: Duration,
+) {
+    let interval = (max_tick_duration / 10).max(Duration::from_millis(1));
+
+    loop {
+        thread::sleep(interval);
+
+        let progress = match progress.upgrade() {
+            Some(progress) => progress,
+            None => return,
+        };
+        let progress = lock(&progress);
+
+        let tick_no = match progress.tick {
+            Some((tick_no, started)) if started.elapsed() > max_tick_duration => tick_no,
+            _ => continue,
+        };
+
+        eprintln!(
+            "FUZZ: tick {} exceeded its time budget of {:?}",
+            tick_no, max_tick_duration
+        );
+        eprintln!("\tstage: {}", StageInfo::describe(&stages, progress.stage));
+
+        std::process::abort();
+    }
+}
@@ -1,52 +1,122 @@
+use std::time::Duration;
+
 use bevy::{
     app::{AppExit, Events, ManualEventReader},
+    ecs::schedule::ShouldRun,
     input::{
+        gamepad::GamepadEventRaw,
         keyboard::KeyboardInput,
         mouse::{MouseButtonInput, MouseMotion, MouseWheel},
+        touch::TouchInput,
     },
     prelude::*,
-    window::WindowResized,
+    window::{
+        CursorEntered, CursorLeft, FileDragAndDrop, ReceivedCharacter,
+        WindowBackendScaleFactorChanged, WindowCloseRequested, WindowCreated, WindowFocused,
+        WindowMoved, WindowResized, WindowScaleFactorChanged, Windows,
+    },
 };
 
 use crate::data::FuzzData;
 use crate::fuzz_input::FuzzInput;
+use crate::invariant::InvariantRegistry;
+use crate::progress::RunnerProgress;
+use crate::snapshot::WorldSnapshot;
+use crate::time::FuzzClock;
+use crate::window::{FilePathPool, WindowIdMap};
 
-const CORE_STAGES: &[CoreStage] = &[
-    CoreStage::First,
-    CoreStage::PreUpdate,
-    CoreStage::Update,
-    CoreStage::PostUpdate,
-    CoreStage::Last,
-];
+/// Settings of the fuzz runner, provided by the `FuzzTarget`
+#[derive(Clone, Default)]
+pub struct FuzzRunnerSettings {
+    /// Whether sub-apps are updated after the main schedule on every tick
+    pub tick_sub_apps: bool,
+    /// Ticks after which the iteration ends, even if there is input left
+    pub max_ticks: Option<usize>,
+    /// Wall time after which a tick is considered stuck, and the process is aborted
+    pub max_tick_duration: Option<Duration>,
+}
+
+/// Run criteria for the startup stage, which is run by the fuzz runner on each reset instead
+pub(crate) fn never_run() -> ShouldRun {
+    ShouldRun::No
+}
 
 pub fn fuzz_runner(app: &mut App) {
+    run_iteration(app, |_, _| {});
+}
+
+/// Runs one fuzz iteration, calling `on_tick` with the tick number after every tick
+pub(crate) fn run_iteration(app: &mut App, mut on_tick: impl FnMut(&App, usize)) {
+    if let Some(progress) = app.world.get_resource::<RunnerProgress>() {
+        let data = app.world.get_resource::<FuzzData>().unwrap();
+        progress.iteration_started(&data.fuzz_inputs);
+    }
+
     // reset the world (TODO: first run is not necessary)
     reset_app_world(app);
 
     let mut app_exit_event_reader = ManualEventReader::<AppExit>::default();
 
     let mut tick_no = 0;
-    while let Ok(_) = tick(app, tick_no, &mut app_exit_event_reader) {
+    loop {
+        if let Some(progress) = app.world.get_resource::<RunnerProgress>() {
+            progress.tick_started(tick_no);
+        }
+
+        let result = tick(app, tick_no, &mut app_exit_event_reader);
+
+        if let Some(progress) = app.world.get_resource::<RunnerProgress>() {
+            progress.invariants_started();
+        }
+        check_invariants(app);
+
+        if let Some(progress) = app.world.get_resource::<RunnerProgress>() {
+            progress.tick_finished();
+        }
+
+        on_tick(app, tick_no);
+
+        if result.is_err() {
+            break;
+        }
         tick_no += 1;
     }
+
+    if let Some(progress) = app.world.get_resource::<RunnerProgress>() {
+        progress.iteration_finished();
+    }
 }
 
 /// Tick (update) a frame
 fn tick(
     app: &mut App,
-    _tick_no: usize,
+    tick_no: usize,
     app_exit_event_reader: &mut ManualEventReader<AppExit>,
 ) -> Result<(), AppExit> {
+    let settings = app
+        .world
+        .get_resource::<FuzzRunnerSettings>()
+        .cloned()
+        .unwrap_or_default();
+
     // Feed the fuzzing input events into the world
     feed_fuzz_events(app);
 
-    // Run only core stages
-    for stage in CORE_STAGES {
-        app.schedule
-            .stage(stage.clone(), |stage: &mut SystemStage| {
-                stage.run(&mut app.world);
-                stage
-            });
+    if let Some(progress) = app.world.get_resource::<RunnerProgress>() {
+        let data = app.world.get_resource::<FuzzData>().unwrap();
+        progress.inputs_consumed(data.last_index_id.unwrap_or(0));
+    }
+
+    // Run all stages but startup, and optionally the sub-apps
+    if settings.tick_sub_apps {
+        app.update();
+    } else {
+        app.schedule.run(&mut app.world);
+    }
+
+    // return `AppExit` if the tick budget of the iteration is used up
+    if matches!(settings.max_ticks, Some(max_ticks) if tick_no + 1 >= max_ticks) {
+        return Err(AppExit);
     }
 
     // return `AppExit` if there is no more fuzzing input to feed
@@ -63,11 +133,40 @@ fn tick(
     Ok(())
 }
 
-/// Should reset the App to the initial state
+/// Panics if an invariant registered by the `FuzzTarget` is violated
+///
+/// The message leaves out the tick (printed by the panic report), so that it stays the same
+/// while the inputs are minimized.
+fn check_invariants(app: &mut App) {
+    if let Some(mut invariants) = app.world.remove_resource::<InvariantRegistry>() {
+        let violation = invariants.check(&mut app.world);
+        app.world.insert_resource(invariants);
+
+        if let Some((name, message)) = violation {
+            panic!("FUZZ: invariant '{}' violated: {}", name, message);
+        }
+    }
+}
+
+/// Resets the App to the state right after it was built
 fn reset_app_world(app: &mut App) {
-    // TODO: is this really needed?
+    // every iteration starts from the same point in (virtual) time, also for startup systems
+    if let Some(mut clock) = app.world.get_resource_mut::<FuzzClock>() {
+        clock.reset();
+    }
+
     app.world.clear_entities();
 
+    // the headless windows are created again, with the same ids, on the first tick
+    if app.world.contains_resource::<WindowIdMap>() {
+        app.world.insert_resource(Windows::default());
+    }
+
+    if let Some(snapshot) = app.world.remove_resource::<WorldSnapshot>() {
+        snapshot.restore(&mut app.world);
+        app.world.insert_resource(snapshot);
+    }
+
     app.schedule
         .stage(CoreStage::First, |stage: &mut SystemStage| {
             stage.run(&mut app.world);
@@ -80,19 +179,26 @@ fn reset_app_world(app: &mut App) {
             schedule
         });
 
-    for stage in CORE_STAGES {
-        app.schedule
-            .stage(stage.clone(), |stage: &mut SystemStage| {
-                stage.run(&mut app.world);
-                stage
-            });
-    }
+    app.schedule.run(&mut app.world);
 }
 
-/// Will send the fuzz input events to world until `FuzzInput::RunFrame` is detected
+/// Will send the fuzz input events to world until a frame end (`FuzzInput::RunFrame`) is detected
 fn feed_fuzz_events(app: &mut App) {
     let world = app.world.cell();
     let mut data = world.get_resource_mut::<FuzzData>().unwrap();
+    let mut clock = world.get_resource_mut::<FuzzClock>();
+    let mut window_ids = world
+        .get_resource_mut::<WindowIdMap>()
+        .expect("Missing WindowIdMap (provided by bevy_fuzz::FuzzTarget) from the App");
+    let file_paths = world
+        .get_resource::<FilePathPool>()
+        .expect("Missing FilePathPool (provided by bevy_fuzz::FuzzTarget) from the App");
+    let mut windows = world
+        .get_resource_mut::<Windows>()
+        .expect("Missing Windows (provided by bevy::window::WindowPlugin) from the App");
+    let mut window_created_events = world.get_resource_mut::<Events<WindowCreated>>().expect(
+        "Missing WindowCreated events (provided by bevy::window::WindowPlugin) from the App",
+    );
 
     // event senders
     let mut mouse_button_input_events =
@@ -114,6 +220,43 @@ fn feed_fuzz_events(app: &mut App) {
     let mut window_resized_events = world.get_resource_mut::<Events<WindowResized>>().expect(
         "Missing WindowResized events (provided by bevy::window::WindowPlugin) from the App",
     );
+    let mut gamepad_events = world.get_resource_mut::<Events<GamepadEventRaw>>().expect(
+        "Missing GamepadEventRaw events (provided by bevy::input::InputPlugin) from the App",
+    );
+    let mut touch_input_events = world
+        .get_resource_mut::<Events<TouchInput>>()
+        .expect("Missing TouchInput events (provided by bevy::input::InputPlugin) from the App");
+    let mut character_events = world
+        .get_resource_mut::<Events<ReceivedCharacter>>()
+        .expect(
+        "Missing ReceivedCharacter events (provided by bevy::window::WindowPlugin) from the App",
+    );
+    let mut window_focused_events = world.get_resource_mut::<Events<WindowFocused>>().expect(
+        "Missing WindowFocused events (provided by bevy::window::WindowPlugin) from the App",
+    );
+    let mut window_moved_events = world
+        .get_resource_mut::<Events<WindowMoved>>()
+        .expect("Missing WindowMoved events (provided by bevy::window::WindowPlugin) from the App");
+    let mut scale_factor_events = world
+        .get_resource_mut::<Events<WindowScaleFactorChanged>>()
+        .expect("Missing WindowScaleFactorChanged events (provided by bevy::window::WindowPlugin) from the App");
+    let mut backend_scale_factor_events = world
+        .get_resource_mut::<Events<WindowBackendScaleFactorChanged>>()
+        .expect("Missing WindowBackendScaleFactorChanged events (provided by bevy::window::WindowPlugin) from the App");
+    let mut cursor_entered_events = world.get_resource_mut::<Events<CursorEntered>>().expect(
+        "Missing CursorEntered events (provided by bevy::window::WindowPlugin) from the App",
+    );
+    let mut cursor_left_events = world
+        .get_resource_mut::<Events<CursorLeft>>()
+        .expect("Missing CursorLeft events (provided by bevy::window::WindowPlugin) from the App");
+    let mut close_requested_events = world
+        .get_resource_mut::<Events<WindowCloseRequested>>()
+        .expect(
+        "Missing WindowCloseRequested events (provided by bevy::window::WindowPlugin) from the App",
+    );
+    let mut file_drag_and_drop_events = world.get_resource_mut::<Events<FileDragAndDrop>>().expect(
+        "Missing FileDragAndDrop events (provided by bevy::window::WindowPlugin) from the App",
+    );
 
     // loop
     let mut break_at_idx = None;
@@ -123,31 +266,200 @@ fn feed_fuzz_events(app: &mut App) {
                 mouse_button_input_events.send(wrap_mouse_button_input.into());
             }
             FuzzInput::KeyboardInput(keyboard_input) => {
-                keyboard_input_events.send(KeyboardInput {
-                    scan_code: 0, // FIXME keyboard_input.scan_code,
-                    key_code: match &keyboard_input.key_code {
-                        Some(key_code) => Some(key_code.into()),
-                        None => None,
-                    },
-                    state: (&keyboard_input.state).into(),
-                });
+                keyboard_input_events.send(keyboard_input.into())
             }
+            FuzzInput::Reserved => {}
             FuzzInput::RunFrame => {
                 break_at_idx = Some(idx);
                 break;
             }
+            FuzzInput::RunFrameWithDelta(delta) => {
+                if let Some(clock) = clock.as_mut() {
+                    clock.set_next_delta(*delta);
+                }
+                break_at_idx = Some(idx);
+                break;
+            }
             FuzzInput::MouseWheel(mouse_wheel) => mouse_wheel_input_events.send(mouse_wheel.into()),
             FuzzInput::MouseMotion(delta) => {
                 mouse_motion_events.send(MouseMotion {
                     delta: delta.into(),
                 });
             }
-            FuzzInput::CursorMoved(cursor_moved) => cursor_moved_events.send(cursor_moved.into()),
+            FuzzInput::CursorMoved(cursor_moved) => {
+                cursor_moved_events.send(cursor_moved.to_event(&mut window_ids))
+            }
             FuzzInput::WindowResized(window_resized) => {
-                window_resized_events.send(window_resized.into())
+                window_resized_events.send(window_resized.to_event(&mut window_ids))
+            }
+            FuzzInput::GamepadEvent(gamepad_event) => gamepad_events.send(gamepad_event.into()),
+            FuzzInput::Touch(touch_input) => touch_input_events.send(touch_input.into()),
+            FuzzInput::ReceivedCharacter(received_character) => {
+                character_events.send(received_character.to_event(&mut window_ids))
+            }
+            FuzzInput::WindowFocused(window_focused) => {
+                window_focused_events.send(window_focused.to_event(&mut window_ids))
+            }
+            FuzzInput::WindowMoved(window_moved) => {
+                window_moved_events.send(window_moved.to_event(&mut window_ids))
+            }
+            FuzzInput::WindowScaleFactorChanged(scale_factor_changed) => {
+                scale_factor_events.send(scale_factor_changed.to_event(&mut window_ids))
+            }
+            FuzzInput::WindowBackendScaleFactorChanged(scale_factor_changed) => {
+                backend_scale_factor_events.send(scale_factor_changed.to_event(&mut window_ids))
+            }
+            FuzzInput::CursorEntered(cursor_entered) => {
+                cursor_entered_events.send(cursor_entered.to_event(&mut window_ids))
             }
+            FuzzInput::CursorLeft(cursor_left) => {
+                cursor_left_events.send(cursor_left.to_event(&mut window_ids))
+            }
+            FuzzInput::WindowCloseRequested(close_requested) => {
+                close_requested_events.send(close_requested.to_event(&mut window_ids))
+            }
+            FuzzInput::FileDragAndDrop(file_drag_and_drop) => file_drag_and_drop_events
+                .send(file_drag_and_drop.to_event(&mut window_ids, &file_paths)),
         }
     }
 
+    window_ids.create_missing_windows(&mut windows, &mut window_created_events);
+
     data.set_last_idx(break_at_idx);
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::window::WindowId;
+
+    use super::*;
+    use crate::{time::FuzzTime, FuzzTarget, SnapshotRegistry};
+
+    #[derive(Default)]
+    struct StartupTimes(Vec<Duration>);
+
+    fn record_startup_time(time: FuzzTime, mut times: ResMut<StartupTimes>) {
+        times.0.push(time.time_since_startup());
+    }
+
+    struct TimedApp;
+
+    impl Plugin for TimedApp {
+        fn build(&self, app: &mut App) {
+            app.init_resource::<StartupTimes>()
+                .add_startup_system(record_startup_time);
+        }
+    }
+
+    impl FuzzTarget for TimedApp {
+        fn snapshot(&self, registry: &mut SnapshotRegistry) {
+            registry.skip_resource::<StartupTimes>();
+        }
+    }
+
+    #[test]
+    fn test_reset_rewinds_clock() {
+        let mut app = App::new();
+        TimedApp.enable_fuzzing_mode(&mut app);
+
+        for frames in [5, 2] {
+            app.insert_resource(FuzzData::new(vec![FuzzInput::RunFrame; frames]));
+            fuzz_runner(&mut app);
+        }
+
+        let times = &app.world.get_resource::<StartupTimes>().unwrap().0;
+        assert_eq!(times.len(), 2);
+        assert_eq!(times[0], times[1]);
+    }
+
+    #[test]
+    fn test_time_is_frozen() {
+        let mut app = App::new();
+        TimedApp.enable_fuzzing_mode(&mut app);
+
+        app.insert_resource(FuzzData::new(vec![FuzzInput::RunFrame; 3]));
+        fuzz_runner(&mut app);
+
+        let time = app.world.get_resource::<Time>().unwrap();
+        assert_eq!(time.delta(), Duration::from_secs(0));
+        assert_eq!(time.time_since_startup(), Duration::from_secs(0));
+    }
+
+    #[derive(Default)]
+    struct CreatedWindows(Vec<Vec<WindowId>>);
+
+    fn record_created_windows(
+        mut created: EventReader<WindowCreated>,
+        mut windows: ResMut<CreatedWindows>,
+    ) {
+        let ids = created.iter().map(|event| event.id).collect::<Vec<_>>();
+        if !ids.is_empty() {
+            windows.0.push(ids);
+        }
+    }
+
+    struct WindowedApp;
+
+    impl Plugin for WindowedApp {
+        fn build(&self, app: &mut App) {
+            app.init_resource::<CreatedWindows>()
+                .add_system(record_created_windows);
+        }
+    }
+
+    impl FuzzTarget for WindowedApp {
+        fn window_count(&self) -> usize {
+            2
+        }
+
+        fn snapshot(&self, registry: &mut SnapshotRegistry) {
+            registry.skip_resource::<CreatedWindows>();
+        }
+    }
+
+    #[test]
+    fn test_reset_recreates_windows() {
+        let mut app = App::new();
+        WindowedApp.enable_fuzzing_mode(&mut app);
+
+        for _ in 0..2 {
+            app.insert_resource(FuzzData::new(vec![FuzzInput::RunFrame; 2]));
+            fuzz_runner(&mut app);
+        }
+
+        // every iteration creates the same windows
+        let created = &app.world.get_resource::<CreatedWindows>().unwrap().0;
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0].len(), 2);
+        assert_eq!(created[0], created[1]);
+    }
+
+    #[derive(Component, Clone)]
+    struct Built;
+
+    struct PluginApp;
+
+    impl Plugin for PluginApp {
+        fn build(&self, app: &mut App) {
+            app.add_plugin(crate::FuzzPlugin::new());
+            app.world.spawn().insert(Built);
+        }
+    }
+
+    impl FuzzTarget for PluginApp {
+        fn snapshot(&self, registry: &mut SnapshotRegistry) {
+            registry.clone_component::<Built>();
+        }
+    }
+
+    #[test]
+    fn test_reset_keeps_snapshot_entities() {
+        let mut app = App::new();
+        PluginApp.enable_fuzzing_mode(&mut app);
+
+        app.insert_resource(FuzzData::new(vec![FuzzInput::RunFrame]));
+        fuzz_runner(&mut app);
+
+        assert_eq!(app.world.query::<&Built>().iter(&app.world).count(), 1);
+    }
+}
@@ -0,0 +1,236 @@
+//! Line-oriented script language for authoring input sequences
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! press A                  # key press (key names as in `KeyCode`)
+//! release A
+//! type hello               # one `ReceivedCharacter` per character
+//! move 10,20               # cursor move
+//! click left at 100,200    # move, press, one frame, release
+//! drag 10,10 -> 300,40     # move, press left, frame, move, frame, release left
+//! scroll 0,-1              # mouse wheel, in lines
+//! wait 3 frames            # `wait 1 frame` and `frame` also work
+//! wait 500 ms              # one frame, lasting 500 ms of simulated time
+//! ```
+
+use crate::{
+    format::ParseError,
+    fuzz_input::FuzzInput,
+    input::{
+        keyboard::{WrapKeyCode, WrapKeyboardInput},
+        mouse::{WrapMouseButton, WrapMouseButtonInput, WrapMouseScrollUnit, WrapMouseWheel},
+        WrapElementState,
+    },
+    math::WrapVec2,
+    window::{
+        event::{WrapCursorMoved, WrapReceivedCharacter},
+        WrapWindowId,
+    },
+};
+
+/// Compiles a script into input events
+pub fn compile_script(script: &str) -> Result<Vec<FuzzInput>, ParseError> {
+    let mut inputs = Vec::new();
+
+    for (line_no, line) in script.lines().enumerate() {
+        let line = match line.find('#') {
+            Some(idx) => &line[..idx],
+            None => line,
+        }
+        .trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        compile_line(line, &mut inputs).map_err(|message| ParseError::InvalidScript {
+            line: line_no + 1,
+            message,
+        })?;
+    }
+
+    Ok(inputs)
+}
+
+fn compile_line(line: &str, inputs: &mut Vec<FuzzInput>) -> Result<(), String> {
+    let (command, rest) = match line.split_once(char::is_whitespace) {
+        Some((command, rest)) => (command, rest.trim()),
+        None => (line, ""),
+    };
+
+    match command {
+        "press" => inputs.push(key(parse_key(rest)?, WrapElementState::Pressed)),
+        "release" => inputs.push(key(parse_key(rest)?, WrapElementState::Released)),
+        "type" => inputs.extend(rest.chars().map(|char| {
+            FuzzInput::ReceivedCharacter(WrapReceivedCharacter {
+                id: WrapWindowId::primary(),
+                char,
+            })
+        })),
+        "move" => inputs.push(cursor_moved(parse_point(rest)?)),
+        "click" => {
+            let (button, point) = rest
+                .split_once(" at ")
+                .ok_or_else(|| format!("expected 'click <button> at x,y', got '{}'", line))?;
+            let button = parse_button(button.trim())?;
+
+            inputs.push(cursor_moved(parse_point(point)?));
+            inputs.push(mouse_button(button.clone(), WrapElementState::Pressed));
+            inputs.push(FuzzInput::RunFrame);
+            inputs.push(mouse_button(button, WrapElementState::Released));
+        }
+        "drag" => {
+            let (from, to) = rest
+                .split_once("->")
+                .ok_or_else(|| format!("expected 'drag x,y -> x,y', got '{}'", line))?;
+            let (from, to) = (parse_point(from)?, parse_point(to)?);
+            let delta = WrapVec2 {
+                x: to.x - from.x,
+                y: to.y - from.y,
+            };
+
+            inputs.push(cursor_moved(from));
+            inputs.push(mouse_button(
+                WrapMouseButton::Left,
+                WrapElementState::Pressed,
+            ));
+            inputs.push(FuzzInput::RunFrame);
+            inputs.push(FuzzInput::MouseMotion(delta));
+            inputs.push(cursor_moved(to));
+            inputs.push(FuzzInput::RunFrame);
+            inputs.push(mouse_button(
+                WrapMouseButton::Left,
+                WrapElementState::Released,
+            ));
+        }
+        "scroll" => {
+            let point = parse_point(rest)?;
+            inputs.push(FuzzInput::MouseWheel(WrapMouseWheel {
+                unit: WrapMouseScrollUnit::Line,
+                x: point.x,
+                y: point.y,
+            }));
+        }
+        "wait" => {
+            let count = match rest.split_whitespace().collect::<Vec<_>>().as_slice() {
+                [count, "frame" | "frames"] => count
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid frame count '{}'", count))?,
+                [millis, "ms"] => {
+                    let millis = millis
+                        .parse::<f32>()
+                        .map_err(|_| format!("invalid duration '{}'", millis))?;
+                    inputs.push(FuzzInput::RunFrameWithDelta(millis / 1000.0));
+                    return Ok(());
+                }
+                _ => return Err(format!("expected 'wait <n> frames', got '{}'", line)),
+            };
+            inputs.extend((0..count).map(|_| FuzzInput::RunFrame));
+        }
+        "frame" => inputs.push(FuzzInput::RunFrame),
+        _ => return Err(format!("unknown command '{}'", command)),
+    }
+
+    Ok(())
+}
+
+fn key(key_code: WrapKeyCode, state: WrapElementState) -> FuzzInput {
+    FuzzInput::KeyboardInput(WrapKeyboardInput {
+        scan_code: 0,
+        key_code: Some(key_code),
+        state,
+    })
+}
+
+fn mouse_button(button: WrapMouseButton, state: WrapElementState) -> FuzzInput {
+    FuzzInput::MouseButton(WrapMouseButtonInput { button, state })
+}
+
+fn cursor_moved(position: WrapVec2) -> FuzzInput {
+    FuzzInput::CursorMoved(WrapCursorMoved {
+        id: WrapWindowId::primary(),
+        position,
+    })
+}
+
+fn parse_key(name: &str) -> Result<WrapKeyCode, String> {
+    ron::de::from_str(name).map_err(|_| format!("unknown key '{}'", name))
+}
+
+fn parse_button(name: &str) -> Result<WrapMouseButton, String> {
+    match name {
+        "left" => Ok(WrapMouseButton::Left),
+        "right" => Ok(WrapMouseButton::Right),
+        "middle" => Ok(WrapMouseButton::Middle),
+        _ => name
+            .parse()
+            .map(WrapMouseButton::Other)
+            .map_err(|_| format!("unknown mouse button '{}'", name)),
+    }
+}
+
+fn parse_point(point: &str) -> Result<WrapVec2, String> {
+    let (x, y) = point
+        .split_once(',')
+        .ok_or_else(|| format!("expected 'x,y', got '{}'", point.trim()))?;
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<f32>()
+            .map_err(|_| format!("invalid coordinate '{}'", v.trim()))
+    };
+
+    Ok(WrapVec2 {
+        x: parse(x)?,
+        y: parse(y)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_script() {
+        let inputs = compile_script(
+            "
+            # open the menu
+            press A
+            wait 2 frames
+            release A   # done
+            click left at 100,200
+            frame
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            inputs,
+            vec![
+                key(WrapKeyCode::A, WrapElementState::Pressed),
+                FuzzInput::RunFrame,
+                FuzzInput::RunFrame,
+                key(WrapKeyCode::A, WrapElementState::Released),
+                cursor_moved(WrapVec2 { x: 100.0, y: 200.0 }),
+                mouse_button(WrapMouseButton::Left, WrapElementState::Pressed),
+                FuzzInput::RunFrame,
+                mouse_button(WrapMouseButton::Left, WrapElementState::Released),
+                FuzzInput::RunFrame,
+            ]
+        );
+
+        assert_eq!(compile_script("drag 10,10 -> 300,40").unwrap().len(), 7);
+        assert_eq!(compile_script("type hé").unwrap().len(), 2);
+        assert_eq!(
+            compile_script("wait 500 ms").unwrap(),
+            vec![FuzzInput::RunFrameWithDelta(0.5)]
+        );
+
+        assert_eq!(
+            compile_script("press A\npress Nope"),
+            Err(ParseError::InvalidScript {
+                line: 2,
+                message: String::from("unknown key 'Nope'")
+            })
+        );
+    }
+}
@@ -0,0 +1,411 @@
+use std::any::TypeId;
+
+use bevy::{
+    app::{AppExit, Events, ScheduleRunnerSettings},
+    core::{FixedTimesteps, Time},
+    ecs::{component::Component, system::Resource},
+    input::{
+        gamepad::{
+            GamepadAxis, GamepadButton, GamepadEvent, GamepadEventRaw, GamepadSettings, Gamepads,
+        },
+        keyboard::KeyboardInput,
+        mouse::{MouseButtonInput, MouseMotion, MouseWheel},
+        touch::{TouchInput, Touches},
+    },
+    prelude::*,
+    reflect::TypeRegistry,
+    tasks::{AsyncComputeTaskPool, ComputeTaskPool, IoTaskPool},
+    window::{
+        CloseWindow, CreateWindow, CursorEntered, CursorLeft, FileDragAndDrop, ReceivedCharacter,
+        WindowBackendScaleFactorChanged, WindowCloseRequested, WindowCreated, WindowFocused,
+        WindowMoved, WindowResized, WindowScaleFactorChanged, Windows,
+    },
+};
+
+use crate::{
+    data::FuzzData,
+    runner::FuzzRunnerSettings,
+    time::FuzzClock,
+    window::{FilePathPool, WindowIdMap},
+};
+
+type Restore = Box<dyn Fn(&mut World) + Send + Sync>;
+type CaptureResource = Box<dyn Fn(&World) -> Restore + Send + Sync>;
+type RestoreComponent = Box<dyn Fn(&mut World, Entity) + Send + Sync>;
+type CaptureComponent = fn(&World, Entity) -> Option<RestoreComponent>;
+
+/// Resources and components that are restored before each fuzz iteration
+///
+/// Components registered as reflected (`#[reflect(Component)]`) are restored by default, other
+/// components are cloned with `clone_component`, and `skip_component` opts a component out.
+/// Bevy can not reflect resources, so each one is either cloned (`clone_resource`), reset in
+/// place (`reset_resource`) or left as is (`skip_resource`). Anything else in the world is
+/// reported by `WorldSnapshot::unrestored`.
+pub struct SnapshotRegistry {
+    resources: Vec<(TypeId, CaptureResource)>,
+    skipped: Vec<TypeId>,
+    components: Vec<(TypeId, CaptureComponent)>,
+    skipped_components: Vec<TypeId>,
+}
+
+impl SnapshotRegistry {
+    /// Restores the resource to its value after the app was built, or removes it if it did not
+    /// exist yet
+    pub fn clone_resource<T: Resource + Clone>(&mut self) -> &mut Self {
+        self.add_resource::<T>(Box::new(|world| match world.get_resource::<T>() {
+            Some(value) => {
+                let value = value.clone();
+                Box::new(move |world: &mut World| world.insert_resource(value.clone()))
+            }
+            None => Box::new(|world: &mut World| {
+                world.remove_resource::<T>();
+            }),
+        }))
+    }
+
+    /// Resets the resource in place with `reset`, for resources that can not be cloned
+    pub fn reset_resource<T: Resource>(&mut self, reset: fn(&mut T)) -> &mut Self {
+        self.add_resource::<T>(Box::new(move |_| {
+            Box::new(move |world: &mut World| {
+                if let Some(mut value) = world.get_resource_mut::<T>() {
+                    reset(&mut value);
+                }
+            })
+        }))
+    }
+
+    /// Opts the resource out of restoring, it keeps its state between iterations
+    pub fn skip_resource<T: Resource>(&mut self) -> &mut Self {
+        self.skipped.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Clones the component of entities that exist after the app was built, for components that
+    /// are not reflected
+    pub fn clone_component<C: Component + Clone>(&mut self) -> &mut Self {
+        self.components
+            .push((TypeId::of::<C>(), capture_component::<C>));
+        self
+    }
+
+    /// Opts the component out of restoring, restored entities do not get it
+    pub fn skip_component<C: Component>(&mut self) -> &mut Self {
+        self.skipped_components.push(TypeId::of::<C>());
+        self
+    }
+
+    fn add_resource<T: Resource>(&mut self, capture: CaptureResource) -> &mut Self {
+        self.resources.push((TypeId::of::<T>(), capture));
+        self
+    }
+
+    fn reset_events<T: Resource>(&mut self) -> &mut Self {
+        self.reset_resource::<Events<T>>(Events::clear)
+    }
+
+    fn is_registered(&self, type_id: TypeId) -> bool {
+        self.skipped.contains(&type_id) || self.resources.iter().any(|(id, _)| *id == type_id)
+    }
+}
+
+impl Default for SnapshotRegistry {
+    fn default() -> Self {
+        let mut registry = SnapshotRegistry {
+            resources: Vec::new(),
+            skipped: Vec::new(),
+            components: Vec::new(),
+            skipped_components: Vec::new(),
+        };
+
+        // bevy_fuzz state, handled by the runner
+        registry
+            .skip_resource::<FuzzData>()
+            .skip_resource::<FuzzClock>()
+            .skip_resource::<WindowIdMap>()
+            .skip_resource::<FilePathPool>()
+            .skip_resource::<FuzzRunnerSettings>()
+            .skip_resource::<Windows>();
+
+        // bevy internals without iteration state
+        registry
+            .skip_resource::<Time>()
+            .skip_resource::<ScheduleRunnerSettings>()
+            .skip_resource::<FixedTimesteps>()
+            .skip_resource::<TypeRegistry>()
+            .skip_resource::<AsyncComputeTaskPool>()
+            .skip_resource::<ComputeTaskPool>()
+            .skip_resource::<IoTaskPool>();
+
+        // bevy input state
+        registry
+            .reset_resource::<Input<KeyCode>>(|input| *input = Input::default())
+            .reset_resource::<Input<MouseButton>>(|input| *input = Input::default())
+            .reset_resource::<Input<GamepadButton>>(|input| *input = Input::default())
+            .reset_resource::<Axis<GamepadAxis>>(|axis| *axis = Axis::default())
+            .reset_resource::<Axis<GamepadButton>>(|axis| *axis = Axis::default())
+            .reset_resource::<Gamepads>(|gamepads| *gamepads = Gamepads::default())
+            .clone_resource::<Touches>()
+            .skip_resource::<GamepadSettings>();
+
+        registry
+            .reset_events::<AppExit>()
+            .reset_events::<KeyboardInput>()
+            .reset_events::<MouseButtonInput>()
+            .reset_events::<MouseMotion>()
+            .reset_events::<MouseWheel>()
+            .reset_events::<GamepadEvent>()
+            .reset_events::<GamepadEventRaw>()
+            .reset_events::<TouchInput>()
+            .reset_events::<ReceivedCharacter>()
+            .reset_events::<CursorMoved>()
+            .reset_events::<CursorEntered>()
+            .reset_events::<CursorLeft>()
+            .reset_events::<WindowResized>()
+            .reset_events::<CreateWindow>()
+            .reset_events::<CloseWindow>()
+            .reset_events::<WindowCreated>()
+            .reset_events::<WindowFocused>()
+            .reset_events::<WindowMoved>()
+            .reset_events::<WindowScaleFactorChanged>()
+            .reset_events::<WindowBackendScaleFactorChanged>()
+            .reset_events::<WindowCloseRequested>()
+            .reset_events::<FileDragAndDrop>();
+
+        registry
+    }
+}
+
+fn capture_component<C: Component + Clone>(
+    world: &World,
+    entity: Entity,
+) -> Option<RestoreComponent> {
+    let component = world.get::<C>(entity)?.clone();
+    Some(Box::new(move |world: &mut World, entity: Entity| {
+        world.entity_mut(entity).insert(component.clone());
+    }))
+}
+
+/// How a component is restored
+enum ComponentCapture {
+    Clone(CaptureComponent),
+    Reflect(ReflectComponent),
+    Skip,
+}
+
+impl ComponentCapture {
+    fn capture(&self, world: &World, entity: Entity) -> Option<RestoreComponent> {
+        match self {
+            ComponentCapture::Clone(capture) => capture(world, entity),
+            ComponentCapture::Reflect(reflect_component) => {
+                let value = reflect_component
+                    .reflect_component(world, entity)?
+                    .clone_value();
+                let reflect_component = reflect_component.clone();
+                Some(Box::new(move |world: &mut World, entity: Entity| {
+                    reflect_component.add_component(world, entity, &*value);
+                }))
+            }
+            ComponentCapture::Skip => None,
+        }
+    }
+}
+
+/// State of the world after the app was built, restored before each fuzz iteration
+pub struct WorldSnapshot {
+    resources: Vec<Restore>,
+    entities: Vec<Vec<RestoreComponent>>,
+    unrestored: Vec<String>,
+}
+
+impl WorldSnapshot {
+    pub fn capture(world: &World, registry: &SnapshotRegistry) -> Self {
+        let resources = registry
+            .resources
+            .iter()
+            .map(|(_, capture)| capture(world))
+            .collect();
+
+        let components = world.components();
+        let type_name_of = |component_id| {
+            let info = components.get_info(component_id).unwrap();
+            (info.type_id(), info.name().to_string())
+        };
+        let types = world
+            .get_resource::<TypeRegistry>()
+            .map(|types| types.read());
+
+        let mut entities = Vec::new();
+        let mut unrestored = Vec::new();
+        for archetype in world.archetypes().iter() {
+            if archetype.is_empty() {
+                continue;
+            }
+
+            let mut captures = Vec::new();
+            for (type_id, name) in archetype.components().map(type_name_of) {
+                let cloned = registry
+                    .components
+                    .iter()
+                    .find(|(id, _)| Some(*id) == type_id);
+                let reflected = type_id.and_then(|type_id| {
+                    types
+                        .as_ref()?
+                        .get_type_data::<ReflectComponent>(type_id)
+                        .cloned()
+                });
+
+                let skipped = type_id
+                    .filter(|type_id| registry.skipped_components.contains(type_id))
+                    .is_some();
+                let capture = if skipped {
+                    ComponentCapture::Skip
+                } else if let Some((_, capture)) = cloned {
+                    ComponentCapture::Clone(*capture)
+                } else if let Some(reflect_component) = reflected {
+                    ComponentCapture::Reflect(reflect_component)
+                } else {
+                    unrestored.push(format!("component {}", name));
+                    ComponentCapture::Skip
+                };
+                captures.push(capture);
+            }
+
+            for entity in archetype.entities() {
+                entities.push(
+                    captures
+                        .iter()
+                        .filter_map(|capture| capture.capture(world, *entity))
+                        .collect(),
+                );
+            }
+        }
+
+        let resource_ids = world.archetypes().resource().unique_components().indices();
+        for (type_id, name) in resource_ids.map(type_name_of) {
+            if type_id
+                .filter(|type_id| registry.is_registered(*type_id))
+                .is_none()
+            {
+                unrestored.push(format!("resource {}", name));
+            }
+        }
+
+        unrestored.sort();
+        unrestored.dedup();
+
+        WorldSnapshot {
+            resources,
+            entities,
+            unrestored,
+        }
+    }
+
+    /// Resources and components that exist in the world, but are not restored
+    pub fn unrestored(&self) -> &[String] {
+        &self.unrestored
+    }
+
+    /// Restores the resources, and respawns the entities into a world without entities
+    pub(crate) fn restore(&self, world: &mut World) {
+        for restore in &self.resources {
+            restore(world);
+        }
+
+        for components in &self.entities {
+            let entity = world.spawn().id();
+            for restore in components {
+                restore(world, entity);
+            }
+        }
+    }
+
+    /// Prints the resources and components that will leak state between iterations
+    pub(crate) fn report(&self) {
+        if self.unrestored.is_empty() {
+            return;
+        }
+
+        println!("FUZZ: state not restored between iterations (see `FuzzTarget::snapshot`):");
+        for name in &self.unrestored {
+            println!("\t{}", name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Counter(usize);
+
+    struct Unregistered;
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Position(i32);
+
+    #[derive(Component, Reflect, Default, Clone, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Velocity(i32);
+
+    #[derive(Component)]
+    struct Unreflected;
+
+    #[derive(Component)]
+    struct Skipped;
+
+    #[test]
+    fn test_world_snapshot() {
+        let mut world = World::new();
+        world.insert_resource(Counter(1));
+        world.insert_resource(Unregistered);
+        world.spawn().insert(Position(3));
+        world
+            .spawn()
+            .insert(Velocity(7))
+            .insert(Unreflected)
+            .insert(Skipped);
+
+        let types = TypeRegistry::default();
+        types.write().register::<Velocity>();
+        world.insert_resource(types);
+
+        let mut registry = SnapshotRegistry::default();
+        registry
+            .clone_resource::<Counter>()
+            .clone_resource::<Touches>()
+            .clone_component::<Position>()
+            .skip_component::<Skipped>();
+        let snapshot = WorldSnapshot::capture(&world, &registry);
+        assert_eq!(
+            snapshot.unrestored(),
+            &[
+                format!("component {}", std::any::type_name::<Unreflected>()),
+                format!("resource {}", std::any::type_name::<Unregistered>()),
+            ]
+        );
+
+        world.insert_resource(Counter(5));
+        world.insert_resource(Touches::default());
+        world.spawn().insert(Position(4));
+
+        world.clear_entities();
+        snapshot.restore(&mut world);
+
+        assert_eq!(world.get_resource::<Counter>(), Some(&Counter(1)));
+        assert!(world.get_resource::<Touches>().is_none());
+        let positions = world
+            .query::<&Position>()
+            .iter(&world)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![Position(3)]);
+        let velocities = world
+            .query::<&Velocity>()
+            .iter(&world)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(velocities, vec![Velocity(7)]);
+        assert_eq!(world.query::<&Skipped>().iter(&world).count(), 0);
+    }
+}
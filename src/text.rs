@@ -0,0 +1,73 @@
+use crate::{format::ParseError, fuzz_input::FuzzInput, parse_commands, script::compile_script};
+
+/// Serializes input events into the human-readable (RON) recording format
+pub fn export_text(input: &[FuzzInput]) -> String {
+    let config = ron::ser::PrettyConfig::new().new_line(String::from("\n"));
+    ron::ser::to_string_pretty(input, config).unwrap()
+}
+
+/// Parses input events from the human-readable (RON) recording format
+pub fn import_text(input: &str) -> Result<Vec<FuzzInput>, ParseError> {
+    ron::de::from_str(input).map_err(|e| ParseError::InvalidText(e.to_string()))
+}
+
+/// Parses a text recording, an input script or a binary recording
+///
+/// Binary recordings always contain zero bytes (frame terminators), so valid UTF-8 inputs without
+/// them are treated as text: RON if they start with `[`, an input script otherwise.
+pub fn parse_input_file(input: Vec<u8>) -> Result<Vec<FuzzInput>, ParseError> {
+    parse_input_file_with(input, parse_commands)
+}
+
+/// Like `parse_input_file`, parsing binary recordings with `parse_binary`
+pub(crate) fn parse_input_file_with(
+    input: Vec<u8>,
+    parse_binary: fn(Vec<u8>) -> Result<Vec<FuzzInput>, ParseError>,
+) -> Result<Vec<FuzzInput>, ParseError> {
+    if input.contains(&0) {
+        return parse_binary(input);
+    }
+
+    match std::str::from_utf8(&input) {
+        Ok(text) if text.trim_start().starts_with('[') => import_text(text),
+        Ok(text) => compile_script(text),
+        Err(_) => parse_binary(input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        encode_commands,
+        input::{
+            keyboard::{WrapKeyCode, WrapKeyboardInput},
+            WrapElementState,
+        },
+        math::WrapVec2,
+        window::{event::WrapCursorMoved, WrapWindowId},
+    };
+
+    #[test]
+    fn test_text_round_trip() {
+        let inputs = vec![
+            FuzzInput::KeyboardInput(WrapKeyboardInput {
+                scan_code: 30,
+                key_code: Some(WrapKeyCode::A),
+                state: WrapElementState::Pressed,
+            }),
+            FuzzInput::CursorMoved(WrapCursorMoved {
+                id: WrapWindowId::from_index(1),
+                position: WrapVec2 { x: 10.0, y: 20.5 },
+            }),
+            FuzzInput::RunFrame,
+        ];
+
+        let text = export_text(&inputs);
+        assert!(text.contains("id: 1"));
+        assert_eq!(import_text(&text).unwrap(), inputs);
+
+        assert_eq!(parse_input_file(text.into_bytes()).unwrap(), inputs);
+        assert_eq!(parse_input_file(encode_commands(&inputs)).unwrap(), inputs);
+    }
+}
@@ -0,0 +1,175 @@
+use std::{marker::PhantomData, time::Duration};
+
+use bevy::{core::Time, ecs::system::SystemParam, prelude::*};
+
+/// Longest simulated frame, longer (or invalid) deltas from the input are clamped
+const MAX_FRAME_DELTA_SECS: f32 = 60.0;
+
+/// Virtual clock that replaces the wall-clock `Time` while fuzzing or applying inputs
+///
+/// Every tick (one `FuzzInput::RunFrame`) advances the clock by `frame_delta`, or by the delta
+/// of a `FuzzInput::RunFrameWithDelta`. Bevy's `Time` can not be set from outside of bevy, so it
+/// is held at zero instead, and systems that depend on time read it through `FuzzTime`.
+pub struct FuzzClock {
+    frame_delta: Duration,
+    next_delta: Option<Duration>,
+    delta: Duration,
+    elapsed: Duration,
+}
+
+impl FuzzClock {
+    pub fn new(frame_delta: Duration) -> Self {
+        Self {
+            frame_delta,
+            next_delta: None,
+            delta: Duration::from_secs(0),
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    pub fn frame_delta(&self) -> Duration {
+        self.frame_delta
+    }
+
+    /// Delta of the current tick
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Time since the start of the fuzz run
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Rewinds the clock to the start of the run
+    pub(crate) fn reset(&mut self) {
+        self.next_delta = None;
+        self.delta = Duration::from_secs(0);
+        self.elapsed = Duration::from_secs(0);
+    }
+
+    /// Sets the delta of the next tick, in seconds
+    pub(crate) fn set_next_delta(&mut self, seconds: f32) {
+        let seconds = if seconds.is_nan() {
+            0.0
+        } else {
+            seconds.clamp(0.0, MAX_FRAME_DELTA_SECS)
+        };
+
+        self.next_delta = Some(Duration::from_secs_f32(seconds));
+    }
+
+    /// Advances the clock by `delta`
+    pub(crate) fn advance(&mut self, delta: Duration) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+}
+
+/// Advances the fuzz clock, once per tick in `CoreStage::First`
+pub(crate) fn fuzz_clock_system(mut clock: ResMut<FuzzClock>) {
+    let delta = clock.next_delta.take().unwrap_or(clock.frame_delta);
+    clock.advance(delta);
+}
+
+/// Undoes the wall-clock update of bevy's `Time`, once per tick in `CoreStage::First` (after
+/// the exclusive `CoreSystem::Time`)
+///
+/// `Time` can only follow the wall clock, which would make every run differ. Held at its default
+/// it reports no time passing, the same on every run.
+pub(crate) fn freeze_time_system(mut time: ResMut<Time>) {
+    *time = Time::default();
+}
+
+/// Time as seen by the app: the `FuzzClock` while fuzzing or applying inputs, and the
+/// wall-clock `Time` otherwise
+///
+/// Use in place of `Res<Time>` in systems whose behaviour depends on time (e.g. timers), so
+/// that fuzzed runs reproduce exactly.
+#[derive(SystemParam)]
+pub struct FuzzTime<'w, 's> {
+    time: Res<'w, Time>,
+    clock: Option<Res<'w, FuzzClock>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> FuzzTime<'w, 's> {
+    pub fn delta(&self) -> Duration {
+        match &self.clock {
+            Some(clock) => clock.delta(),
+            None => self.time.delta(),
+        }
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta().as_secs_f32()
+    }
+
+    pub fn time_since_startup(&self) -> Duration {
+        match &self.clock {
+            Some(clock) => clock.elapsed(),
+            None => self.time.time_since_startup(),
+        }
+    }
+
+    pub fn seconds_since_startup(&self) -> f64 {
+        self.time_since_startup().as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Seen(Duration, Duration);
+
+    fn read_time_system(time: FuzzTime, mut seen: ResMut<Seen>) {
+        *seen = Seen(time.delta(), time.time_since_startup());
+    }
+
+    #[test]
+    fn test_fuzz_clock() {
+        let mut world = World::new();
+        world.insert_resource(Time::default());
+        world.insert_resource(FuzzClock::new(Duration::from_millis(10)));
+        world.insert_resource(Seen(Duration::MAX, Duration::MAX));
+
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(fuzz_clock_system.label("clock"));
+        stage.add_system(read_time_system.after("clock"));
+
+        stage.run(&mut world);
+        world
+            .get_resource_mut::<FuzzClock>()
+            .unwrap()
+            .set_next_delta(0.5);
+        stage.run(&mut world);
+        let seen = world.get_resource::<Seen>().unwrap();
+        assert_eq!(seen.0, Duration::from_millis(500));
+        assert_eq!(seen.1, Duration::from_millis(510));
+
+        world.get_resource_mut::<FuzzClock>().unwrap().reset();
+        stage.run(&mut world);
+        let seen = world.get_resource::<Seen>().unwrap();
+        assert_eq!(seen.0, Duration::from_millis(10));
+        assert_eq!(seen.1, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_fuzz_clock_next_delta() {
+        let mut clock = FuzzClock::new(Duration::from_millis(10));
+
+        clock.set_next_delta(0.5);
+        assert_eq!(clock.next_delta.take(), Some(Duration::from_millis(500)));
+
+        clock.set_next_delta(-1.0);
+        assert_eq!(clock.next_delta.take(), Some(Duration::from_secs(0)));
+
+        clock.set_next_delta(f32::NAN);
+        assert_eq!(clock.next_delta.take(), Some(Duration::from_secs(0)));
+
+        clock.set_next_delta(f32::INFINITY);
+        assert_eq!(clock.next_delta.take(), Some(Duration::from_secs(60)));
+    }
+}
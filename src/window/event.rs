@@ -1,10 +1,15 @@
-use bevy::window::{CursorMoved, WindowResized};
+use bevy::window::{
+    CursorEntered, CursorLeft, CursorMoved, FileDragAndDrop, ReceivedCharacter,
+    WindowBackendScaleFactorChanged, WindowCloseRequested, WindowFocused, WindowMoved,
+    WindowResized, WindowScaleFactorChanged,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::math::WrapVec2;
-use super::WrapWindowId;
+use super::{FilePathPool, WindowIdMap, WrapFilePath, WrapWindowId};
+use crate::math::{WrapIVec2, WrapVec2};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct WrapWindowResized {
     pub id: WrapWindowId,
     pub width: f32,
@@ -12,47 +17,275 @@ pub struct WrapWindowResized {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct WrapCursorMoved {
     pub id: WrapWindowId,
     pub position: WrapVec2,
 }
 
-impl From<&WrapWindowResized> for WindowResized {
-    fn from(inp: &WrapWindowResized) -> Self {
-        WindowResized {
-            id: (&inp.id).into(),
-            width: inp.width,
-            height: inp.height
-        }
-    }
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapReceivedCharacter {
+    pub id: WrapWindowId,
+    pub char: char,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapWindowFocused {
+    pub id: WrapWindowId,
+    pub focused: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapWindowMoved {
+    pub id: WrapWindowId,
+    pub position: WrapIVec2,
 }
 
-impl From<&WindowResized> for WrapWindowResized {
-    fn from(inp: &WindowResized) -> Self {
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapWindowScaleFactorChanged {
+    pub id: WrapWindowId,
+    pub scale_factor: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapWindowBackendScaleFactorChanged {
+    pub id: WrapWindowId,
+    pub scale_factor: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapCursorEntered {
+    pub id: WrapWindowId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapCursorLeft {
+    pub id: WrapWindowId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WrapWindowCloseRequested {
+    pub id: WrapWindowId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum WrapFileDragAndDrop {
+    DroppedFile {
+        id: WrapWindowId,
+        path: WrapFilePath,
+    },
+    HoveredFile {
+        id: WrapWindowId,
+        path: WrapFilePath,
+    },
+    HoveredFileCancelled {
+        id: WrapWindowId,
+    },
+}
+
+impl WrapWindowResized {
+    pub fn from_event(inp: &WindowResized, window_ids: &mut WindowIdMap) -> Self {
         WrapWindowResized {
-            id: (&inp.id).into(),
+            id: window_ids.wrap(&inp.id),
             width: inp.width,
-            height: inp.height
+            height: inp.height,
+        }
+    }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> WindowResized {
+        WindowResized {
+            id: window_ids.resolve(&self.id),
+            width: self.width,
+            height: self.height,
         }
     }
 }
 
+impl WrapCursorMoved {
+    pub fn from_event(inp: &CursorMoved, window_ids: &mut WindowIdMap) -> Self {
+        WrapCursorMoved {
+            id: window_ids.wrap(&inp.id),
+            position: (&inp.position).into(),
+        }
+    }
 
-impl From<&WrapCursorMoved> for CursorMoved {
-    fn from(inp: &WrapCursorMoved) -> Self {
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> CursorMoved {
         CursorMoved {
-            id: (&inp.id).into(),
-            position: (&inp.position).into(),
+            id: window_ids.resolve(&self.id),
+            position: (&self.position).into(),
         }
     }
 }
 
-impl From<&CursorMoved> for WrapCursorMoved {
-    fn from(inp: &CursorMoved) -> Self {
-        WrapCursorMoved {
-            id: (&inp.id).into(),
+impl WrapReceivedCharacter {
+    pub fn from_event(inp: &ReceivedCharacter, window_ids: &mut WindowIdMap) -> Self {
+        WrapReceivedCharacter {
+            id: window_ids.wrap(&inp.id),
+            char: inp.char,
+        }
+    }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> ReceivedCharacter {
+        ReceivedCharacter {
+            id: window_ids.resolve(&self.id),
+            char: self.char,
+        }
+    }
+}
+
+impl WrapWindowFocused {
+    pub fn from_event(inp: &WindowFocused, window_ids: &mut WindowIdMap) -> Self {
+        WrapWindowFocused {
+            id: window_ids.wrap(&inp.id),
+            focused: inp.focused,
+        }
+    }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> WindowFocused {
+        WindowFocused {
+            id: window_ids.resolve(&self.id),
+            focused: self.focused,
+        }
+    }
+}
+
+impl WrapWindowMoved {
+    pub fn from_event(inp: &WindowMoved, window_ids: &mut WindowIdMap) -> Self {
+        WrapWindowMoved {
+            id: window_ids.wrap(&inp.id),
             position: (&inp.position).into(),
         }
     }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> WindowMoved {
+        WindowMoved {
+            id: window_ids.resolve(&self.id),
+            position: (&self.position).into(),
+        }
+    }
+}
+
+impl WrapWindowScaleFactorChanged {
+    pub fn from_event(inp: &WindowScaleFactorChanged, window_ids: &mut WindowIdMap) -> Self {
+        WrapWindowScaleFactorChanged {
+            id: window_ids.wrap(&inp.id),
+            scale_factor: inp.scale_factor,
+        }
+    }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> WindowScaleFactorChanged {
+        WindowScaleFactorChanged {
+            id: window_ids.resolve(&self.id),
+            scale_factor: self.scale_factor,
+        }
+    }
+}
+
+impl WrapWindowBackendScaleFactorChanged {
+    pub fn from_event(inp: &WindowBackendScaleFactorChanged, window_ids: &mut WindowIdMap) -> Self {
+        WrapWindowBackendScaleFactorChanged {
+            id: window_ids.wrap(&inp.id),
+            scale_factor: inp.scale_factor,
+        }
+    }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> WindowBackendScaleFactorChanged {
+        WindowBackendScaleFactorChanged {
+            id: window_ids.resolve(&self.id),
+            scale_factor: self.scale_factor,
+        }
+    }
+}
+
+impl WrapCursorEntered {
+    pub fn from_event(inp: &CursorEntered, window_ids: &mut WindowIdMap) -> Self {
+        WrapCursorEntered {
+            id: window_ids.wrap(&inp.id),
+        }
+    }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> CursorEntered {
+        CursorEntered {
+            id: window_ids.resolve(&self.id),
+        }
+    }
+}
+
+impl WrapCursorLeft {
+    pub fn from_event(inp: &CursorLeft, window_ids: &mut WindowIdMap) -> Self {
+        WrapCursorLeft {
+            id: window_ids.wrap(&inp.id),
+        }
+    }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> CursorLeft {
+        CursorLeft {
+            id: window_ids.resolve(&self.id),
+        }
+    }
 }
 
+impl WrapWindowCloseRequested {
+    pub fn from_event(inp: &WindowCloseRequested, window_ids: &mut WindowIdMap) -> Self {
+        WrapWindowCloseRequested {
+            id: window_ids.wrap(&inp.id),
+        }
+    }
+
+    pub fn to_event(&self, window_ids: &mut WindowIdMap) -> WindowCloseRequested {
+        WindowCloseRequested {
+            id: window_ids.resolve(&self.id),
+        }
+    }
+}
+
+impl WrapFileDragAndDrop {
+    pub fn from_event(inp: &FileDragAndDrop, window_ids: &mut WindowIdMap) -> Self {
+        match inp {
+            FileDragAndDrop::DroppedFile { id, path_buf } => WrapFileDragAndDrop::DroppedFile {
+                id: window_ids.wrap(id),
+                path: WrapFilePath::Path(path_buf.to_string_lossy().into_owned()),
+            },
+            FileDragAndDrop::HoveredFile { id, path_buf } => WrapFileDragAndDrop::HoveredFile {
+                id: window_ids.wrap(id),
+                path: WrapFilePath::Path(path_buf.to_string_lossy().into_owned()),
+            },
+            FileDragAndDrop::HoveredFileCancelled { id } => {
+                WrapFileDragAndDrop::HoveredFileCancelled {
+                    id: window_ids.wrap(id),
+                }
+            }
+        }
+    }
+
+    pub fn to_event(
+        &self,
+        window_ids: &mut WindowIdMap,
+        file_paths: &FilePathPool,
+    ) -> FileDragAndDrop {
+        match self {
+            WrapFileDragAndDrop::DroppedFile { id, path } => FileDragAndDrop::DroppedFile {
+                id: window_ids.resolve(id),
+                path_buf: file_paths.resolve(path),
+            },
+            WrapFileDragAndDrop::HoveredFile { id, path } => FileDragAndDrop::HoveredFile {
+                id: window_ids.resolve(id),
+                path_buf: file_paths.resolve(path),
+            },
+            WrapFileDragAndDrop::HoveredFileCancelled { id } => {
+                FileDragAndDrop::HoveredFileCancelled {
+                    id: window_ids.resolve(id),
+                }
+            }
+        }
+    }
+}
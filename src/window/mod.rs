@@ -1,29 +1,219 @@
-use bevy::window::WindowId;
-use serde::{Deserialize, Serialize};
+use bevy::{
+    app::Events,
+    window::{Window, WindowCreated, WindowDescriptor, WindowId, Windows},
+};
+use raw_window_handle::{RawWindowHandle, WebHandle};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::PathBuf;
 
 pub mod event;
 
-#[derive(Clone, Serialize, Deserialize, PartialEq)]
+/// Window id, stored as a stable per-recording index (little-endian), `0` being the primary window
+///
+/// The 16-byte layout is kept so that recordings made before multi-window support still load.
+/// Human-readable formats store the plain index instead.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct WrapWindowId([u8; 16]);
 
+impl Serialize for WrapWindowId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_u64(self.index() as u64)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WrapWindowId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            u64::deserialize(deserializer).map(|v| WrapWindowId::from_index(v as usize))
+        } else {
+            <[u8; 16]>::deserialize(deserializer).map(WrapWindowId)
+        }
+    }
+}
+
+impl WrapWindowId {
+    pub fn primary() -> Self {
+        Self::from_index(0)
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        WrapWindowId((index as u128).to_le_bytes())
+    }
+
+    pub fn index(&self) -> u128 {
+        u128::from_le_bytes(self.0)
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.index() == 0
+    }
+}
+
 impl std::fmt::Debug for WrapWindowId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "WrapWindowId(primary)")
+        if self.is_primary() {
+            write!(f, "WrapWindowId(primary)")
+        } else {
+            write!(f, "WrapWindowId({})", self.index())
+        }
+    }
+}
+
+/// Maps bevy `WindowId`s to per-recording indices and back
+///
+/// While recording, windows get an index in the order they are first seen in an event. While
+/// fuzzing or applying, indices are folded into `window_count` windows, whose `WindowId`s are
+/// allocated up front, so that an index maps to the same window in every fuzz iteration.
+pub struct WindowIdMap {
+    ids: Vec<WindowId>,
+    window_count: usize,
+}
+
+impl WindowIdMap {
+    pub fn new(window_count: usize) -> Self {
+        let window_count = window_count.max(1);
+        let mut ids = vec![WindowId::primary()];
+        ids.extend((1..window_count).map(|_| WindowId::new()));
+
+        Self { ids, window_count }
+    }
+
+    /// Returns the index of a window, registering it if it has not been seen before
+    pub fn wrap(&mut self, id: &WindowId) -> WrapWindowId {
+        let index = match self.ids.iter().position(|v| v == id) {
+            Some(index) => index,
+            None => {
+                self.ids.push(*id);
+                self.ids.len() - 1
+            }
+        };
+
+        WrapWindowId::from_index(index)
+    }
+
+    /// Returns the window of an index
+    pub fn resolve(&mut self, id: &WrapWindowId) -> WindowId {
+        let index = (id.index() % self.window_count as u128) as usize;
+
+        while self.ids.len() <= index {
+            self.ids.push(WindowId::new());
+        }
+
+        self.ids[index]
+    }
+
+    /// Adds a headless `Window` for each id that does not yet have one
+    pub(crate) fn create_missing_windows(
+        &self,
+        windows: &mut Windows,
+        window_created_events: &mut Events<WindowCreated>,
+    ) {
+        for id in self.ids.iter() {
+            if windows.get(*id).is_some() {
+                continue;
+            }
+
+            let descriptor = WindowDescriptor::default();
+            windows.add(Window::new(
+                *id,
+                &descriptor,
+                descriptor.width as u32,
+                descriptor.height as u32,
+                1.0,
+                None,
+                RawWindowHandle::Web(WebHandle::empty()),
+            ));
+            window_created_events.send(WindowCreated { id: *id });
+        }
     }
 }
 
-impl From<&WrapWindowId> for WindowId {
-    fn from(inp: &WrapWindowId) -> Self {
-        WindowId::primary() // FIXME
+impl Default for WindowIdMap {
+    fn default() -> Self {
+        Self {
+            ids: vec![WindowId::primary()],
+            window_count: usize::MAX,
+        }
     }
 }
 
-impl From<&WindowId> for WrapWindowId {
-    fn from(inp: &WindowId) -> Self {
-        if !inp.is_primary() {
-            panic!("Only primary window type is supported currently");
+/// File path of a drag-and-drop event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum WrapFilePath {
+    /// A path as it was recorded
+    Path(String),
+    /// Index into the `FuzzTarget::file_path_candidates` pool
+    Candidate(u16),
+}
+
+/// Candidate paths for fuzzed drag-and-drop events, see `FuzzTarget::file_path_candidates`
+#[derive(Default)]
+pub struct FilePathPool(pub Vec<PathBuf>);
+
+impl FilePathPool {
+    pub fn resolve(&self, path: &WrapFilePath) -> PathBuf {
+        match path {
+            WrapFilePath::Path(path) => PathBuf::from(path),
+            WrapFilePath::Candidate(_) if self.0.is_empty() => PathBuf::new(),
+            WrapFilePath::Candidate(index) => self.0[*index as usize % self.0.len()].clone(),
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_id_map() {
+        let secondary = WindowId::new();
+
+        let mut recording = WindowIdMap::default();
+        assert_eq!(
+            recording.wrap(&WindowId::primary()),
+            WrapWindowId::primary()
+        );
+        assert_eq!(recording.wrap(&secondary), WrapWindowId::from_index(1));
+        assert_eq!(recording.wrap(&secondary), WrapWindowId::from_index(1));
+
+        let mut replay = WindowIdMap::new(2);
+        assert_eq!(replay.ids.len(), 2);
+        assert_eq!(
+            replay.resolve(&WrapWindowId::primary()),
+            WindowId::primary()
+        );
+        let resolved = replay.resolve(&WrapWindowId::from_index(1));
+        assert!(!resolved.is_primary());
+        assert_eq!(replay.resolve(&WrapWindowId::from_index(1)), resolved);
+        assert_eq!(
+            replay.resolve(&WrapWindowId::from_index(2)),
+            WindowId::primary()
+        );
+    }
+
+    #[test]
+    fn test_file_path_pool() {
+        let pool = FilePathPool(vec![PathBuf::from("a.png"), PathBuf::from("b.png")]);
+        assert_eq!(
+            pool.resolve(&WrapFilePath::Candidate(1)),
+            PathBuf::from("b.png")
+        );
+        assert_eq!(
+            pool.resolve(&WrapFilePath::Candidate(2)),
+            PathBuf::from("a.png")
+        );
+        assert_eq!(
+            pool.resolve(&WrapFilePath::Path("c.png".into())),
+            PathBuf::from("c.png")
+        );
 
-        WrapWindowId([0u8; 16]) // FIXME
+        let empty = FilePathPool::default();
+        assert_eq!(empty.resolve(&WrapFilePath::Candidate(3)), PathBuf::new());
     }
 }